@@ -0,0 +1,95 @@
+use std::collections::{BTreeSet, HashMap};
+
+use crate::earley::Earley;
+use crate::forest::Tree;
+use crate::syntax::{MixedChar, Rule, Terminal, TerminalString, Variable};
+
+/// Sentence with two different parse trees
+pub struct Ambiguity {
+    pub sentence: Vec<Terminal>,
+    pub trees: (Tree, Tree),
+}
+
+/// Every sentence of at most `max_length` terminals each variable derives,
+/// grown until a fixed point so ε-rules and left recursion need no special care
+fn sentences(rules: &[Rule], max_length: usize) -> HashMap<Variable, BTreeSet<Vec<Terminal>>> {
+    let mut derived: HashMap<Variable, BTreeSet<Vec<Terminal>>> = HashMap::new();
+    loop {
+        let mut changed = false;
+        for rule in rules {
+            let mut partial: BTreeSet<Vec<Terminal>> = BTreeSet::from([vec![]]);
+            for symbol in &rule.output.data {
+                let mut next = BTreeSet::new();
+                for prefix in &partial {
+                    match symbol {
+                        MixedChar::Terminal(terminal) if prefix.len() < max_length => {
+                            let mut sentence = prefix.clone();
+                            sentence.push(*terminal);
+                            next.insert(sentence);
+                        }
+                        MixedChar::Terminal(_) => {}
+                        MixedChar::Variable(variable) => {
+                            for suffix in derived.get(variable).into_iter().flatten() {
+                                if prefix.len() + suffix.len() <= max_length {
+                                    next.insert([prefix.as_slice(), suffix.as_slice()].concat());
+                                }
+                            }
+                        }
+                    }
+                }
+                partial = next;
+            }
+            let entry = derived.entry(rule.clause).or_default();
+            for sentence in partial {
+                changed |= entry.insert(sentence);
+            }
+        }
+        if !changed {
+            return derived;
+        }
+    }
+}
+
+/// Search every sentence of the grammar up to `max_length` terminals for one with two parse trees,
+/// shortest sentences first
+pub fn find_ambiguities(rules: &[Rule], start: Variable, max_length: usize) -> Vec<Ambiguity> {
+    let parser = Earley::new(rules, start);
+    let mut candidates: Vec<Vec<Terminal>> = sentences(rules, max_length).remove(&start).unwrap_or_default().into_iter().collect();
+    candidates.sort_by_key(|sentence| sentence.len());
+
+    let mut output = vec![];
+    for sentence in candidates {
+        let input = TerminalString { data: sentence.clone() };
+        let Some(forest) = parser.parse(&input).forest() else {
+            continue;
+        };
+        let mut trees = forest.trees(2).into_iter();
+        if let (Some(first), Some(second)) = (trees.next(), trees.next()) {
+            output.push(Ambiguity { sentence, trees: (first, second) });
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::find_ambiguities;
+    use crate::syntax::tests::rules;
+    use crate::syntax::Variable;
+
+    #[test]
+    fn ambiguous_grammar() {
+        let found = find_ambiguities(&rules(&["E:E+E", "E:i"]), Variable { symbol: 'E' }, 5);
+        let shortest = found.first().unwrap();
+        let sentence: String = shortest.sentence.iter().map(|terminal| terminal.symbol).collect();
+        assert_eq!(sentence, "i+i+i");
+        assert_ne!(shortest.trees.0, shortest.trees.1);
+        assert_eq!(found.len(), 1);
+    }
+
+    #[test]
+    fn unambiguous_grammar() {
+        let rules = rules(&["E:E+T", "E:T", "T:T*F", "T:F", "F:(E)", "F:i"]);
+        assert!(find_ambiguities(&rules, Variable { symbol: 'E' }, 7).is_empty());
+    }
+}
@@ -0,0 +1,92 @@
+use std::collections::BTreeSet;
+
+use prettytable::{Cell, Row, Table};
+
+use crate::cnf::ChomskyNormalForm;
+use crate::syntax::{MixedChar, Terminal, TerminalString, Variable};
+
+/// Cocke–Younger–Kasami recognizer
+/// https://en.wikipedia.org/wiki/CYK_algorithm
+pub struct CykTable {
+    input: Vec<Terminal>,
+    start: Variable,
+    accept_empty: bool,
+    // cells[length - 1][begin] hold every variable deriving input[begin..begin + length]
+    cells: Vec<Vec<BTreeSet<Variable>>>,
+}
+
+impl CykTable {
+    pub fn new(grammar: &ChomskyNormalForm, input: &TerminalString) -> Self {
+        let input = input.without_end().to_vec();
+        let len = input.len();
+        let mut cells: Vec<Vec<BTreeSet<Variable>>> = (0..len).map(|length| vec![BTreeSet::new(); len - length]).collect();
+
+        for (begin, terminal) in input.iter().enumerate() {
+            for rule in &grammar.rules {
+                if rule.output.data.as_slice() == [MixedChar::Terminal(*terminal)] {
+                    cells[0][begin].insert(rule.clause);
+                }
+            }
+        }
+        for length in 2..=len {
+            for begin in 0..=len - length {
+                for split in 1..length {
+                    for rule in &grammar.rules {
+                        let [MixedChar::Variable(left), MixedChar::Variable(right)] = rule.output.data.as_slice() else {
+                            continue;
+                        };
+                        if cells[split - 1][begin].contains(left) && cells[length - split - 1][begin + split].contains(right) {
+                            cells[length - 1][begin].insert(rule.clause);
+                        }
+                    }
+                }
+            }
+        }
+        Self { input, start: grammar.start, accept_empty: grammar.accept_empty(), cells }
+    }
+
+    pub fn len(&self) -> usize {
+        self.input.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.input.is_empty()
+    }
+
+    pub fn get(&self, begin: usize, length: usize) -> &BTreeSet<Variable> {
+        &self.cells[length - 1][begin]
+    }
+
+    pub fn accepts(&self) -> bool {
+        match self.cells.last() {
+            Some(whole) => whole[0].contains(&self.start),
+            None => self.accept_empty,
+        }
+    }
+
+    /// Table with every span up to `max_length` filled in, longest span on top
+    pub fn table(&self, max_length: usize) -> Table {
+        let mut table = Table::new();
+        for length in (1..=self.len()).rev() {
+            let mut row = vec![Cell::new(&length.to_string())];
+            for begin in 0..=self.len() - length {
+                let text = match length <= max_length {
+                    true => format!("{:?}", self.get(begin, length)),
+                    false => String::new(),
+                };
+                row.push(Cell::new(&text));
+            }
+            table.add_row(Row::new(row));
+        }
+        let mut input_row = vec![Cell::new("")];
+        input_row.extend(self.input.iter().map(|terminal| Cell::new(&terminal.to_string())));
+        table.add_row(Row::new(input_row));
+        table
+    }
+}
+
+impl ChomskyNormalForm {
+    pub fn recognize(&self, input: &TerminalString) -> bool {
+        CykTable::new(self, input).accepts()
+    }
+}
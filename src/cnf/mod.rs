@@ -0,0 +1,244 @@
+mod cyk;
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+pub use cyk::CykTable;
+use crate::first_follow;
+use crate::syntax::{MixedChar, MixedString, Rule, Terminal, Variable};
+
+/// Grammar in Chomsky normal form, every rule is either `A -> BC`, `A -> a` or `S0 -> ε`
+/// https://en.wikipedia.org/wiki/Chomsky_normal_form
+#[derive(Clone, Debug)]
+pub struct ChomskyNormalForm {
+    pub start: Variable,
+    pub rules: Vec<Rule>,
+}
+
+/// Hands out variables which are not used anywhere in the grammar,
+/// upper case letters first so the converted grammar stays readable
+struct FreshVariables {
+    used: HashSet<Variable>,
+    next_private: u32,
+}
+
+impl FreshVariables {
+    fn new(rules: &[Rule], start: Variable) -> Self {
+        let mut used: HashSet<Variable> = rules.iter().map(|rule| rule.clause).collect();
+        used.insert(start);
+        for rule in rules {
+            used.extend(rule.output.data.iter().filter_map(|symbol| Variable::try_from(symbol).ok()));
+        }
+        Self { used, next_private: 0xE000 }
+    }
+
+    fn next(&mut self) -> Variable {
+        let letter = ('A'..='Z').map(|symbol| Variable { symbol }).find(|variable| !self.used.contains(variable));
+        let variable = letter.unwrap_or_else(|| {
+            let symbol = char::from_u32(self.next_private).unwrap();
+            self.next_private += 1;
+            Variable { symbol }
+        });
+        self.used.insert(variable);
+        variable
+    }
+}
+
+fn rule_of(clause: Variable, output: Vec<MixedChar>) -> Rule {
+    Rule { clause, output: MixedString { data: output } }
+}
+
+fn dedup(rules: Vec<Rule>) -> Vec<Rule> {
+    let mut seen = HashSet::new();
+    rules.into_iter().filter(|rule| seen.insert(rule.clone())).collect()
+}
+
+impl ChomskyNormalForm {
+    pub fn from_rules(rules: &[Rule], start: Variable) -> Self {
+        let mut fresh = FreshVariables::new(rules, start);
+        let (start, rules) = Self::start(rules, start, &mut fresh);
+        let rules = Self::term(rules, &mut fresh);
+        let rules = Self::bin(rules, &mut fresh);
+        let rules = Self::del(rules, start);
+        let rules = Self::unit(rules);
+        Self { start, rules }
+    }
+
+    /// START: S0 -> S so the start variable never appears on the right hand side
+    fn start(rules: &[Rule], start: Variable, fresh: &mut FreshVariables) -> (Variable, Vec<Rule>) {
+        let new_start = fresh.next();
+        let mut output = vec![rule_of(new_start, vec![start.into()])];
+        output.extend(rules.iter().cloned());
+        (new_start, output)
+    }
+
+    /// TERM: replace terminals inside rules with at least two symbols by `T -> a`
+    fn term(rules: Vec<Rule>, fresh: &mut FreshVariables) -> Vec<Rule> {
+        let mut replacement: HashMap<Terminal, Variable> = HashMap::new();
+        let mut output = vec![];
+        let mut added = vec![];
+        for mut rule in rules {
+            if rule.len() >= 2 {
+                for symbol in rule.output.data.iter_mut() {
+                    let MixedChar::Terminal(terminal) = *symbol else {
+                        continue;
+                    };
+                    let variable = *replacement.entry(terminal).or_insert_with(|| {
+                        let variable = fresh.next();
+                        added.push(rule_of(variable, vec![terminal.into()]));
+                        variable
+                    });
+                    *symbol = variable.into();
+                }
+            }
+            output.push(rule);
+        }
+        output.append(&mut added);
+        output
+    }
+
+    /// BIN: split `A -> X1 X2 ... Xn` into a chain of rules with two symbols each
+    fn bin(rules: Vec<Rule>, fresh: &mut FreshVariables) -> Vec<Rule> {
+        let mut output = vec![];
+        for rule in rules {
+            if rule.len() <= 2 {
+                output.push(rule);
+                continue;
+            }
+            let symbols = &rule.output.data;
+            let mut clause = rule.clause;
+            for symbol in &symbols[..symbols.len() - 2] {
+                let next = fresh.next();
+                output.push(rule_of(clause, vec![*symbol, next.into()]));
+                clause = next;
+            }
+            output.push(rule_of(clause, symbols[symbols.len() - 2..].to_vec()));
+        }
+        output
+    }
+
+    /// DEL: remove ε-rules, keeping `S0 -> ε` when the language contains the empty string
+    fn del(rules: Vec<Rule>, start: Variable) -> Vec<Rule> {
+        let nullable = first_follow::nullable(&rules);
+        let mut output = vec![];
+        for rule in &rules {
+            let mut variants: Vec<Vec<MixedChar>> = vec![vec![]];
+            for symbol in &rule.output.data {
+                let can_drop = matches!(symbol, MixedChar::Variable(variable) if nullable.contains(variable));
+                let mut next = vec![];
+                for variant in variants {
+                    if can_drop {
+                        next.push(variant.clone());
+                    }
+                    let mut kept = variant;
+                    kept.push(*symbol);
+                    next.push(kept);
+                }
+                variants = next;
+            }
+            for variant in variants {
+                if variant.is_empty() && rule.clause != start {
+                    continue;
+                }
+                output.push(rule_of(rule.clause, variant));
+            }
+        }
+        dedup(output)
+    }
+
+    /// UNIT: replace `A -> B` by the non-unit rules of every variable reachable through unit rules
+    fn unit(rules: Vec<Rule>) -> Vec<Rule> {
+        let unit_target = |rule: &Rule| match rule.output.data.as_slice() {
+            [MixedChar::Variable(variable)] => Some(*variable),
+            _ => None,
+        };
+        let variables: BTreeSet<Variable> = rules.iter().map(|rule| rule.clause).collect();
+        let mut output = vec![];
+        for variable in variables {
+            let mut reachable = BTreeSet::from([variable]);
+            let mut stack = vec![variable];
+            while let Some(current) = stack.pop() {
+                for next in rules.iter().filter(|rule| rule.clause == current).filter_map(unit_target) {
+                    if reachable.insert(next) {
+                        stack.push(next);
+                    }
+                }
+            }
+            for rule in rules.iter().filter(|rule| reachable.contains(&rule.clause) && unit_target(rule).is_none()) {
+                output.push(rule_of(variable, rule.output.data.clone()));
+            }
+        }
+        dedup(output)
+    }
+
+    pub fn accept_empty(&self) -> bool {
+        self.rules.iter().any(|rule| rule.clause == self.start && rule.len() == 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ChomskyNormalForm;
+    use crate::earley::Earley;
+    use crate::syntax::tests::{rules, sentence};
+    use crate::syntax::{MixedChar, Variable};
+
+    fn assert_shape(grammar: &ChomskyNormalForm) {
+        for rule in &grammar.rules {
+            match rule.output.data.as_slice() {
+                [MixedChar::Variable(left), MixedChar::Variable(right)] => {
+                    assert!(*left != grammar.start && *right != grammar.start, "{}", rule);
+                }
+                [MixedChar::Terminal(_)] => {}
+                [] => assert_eq!(rule.clause, grammar.start, "{}", rule),
+                _ => panic!("{} is not in Chomsky normal form", rule),
+            }
+        }
+    }
+
+    /// Every sentence up to `length` over `alphabet` is recognized by cyk exactly when earley accepts it,
+    /// gives the number of sentences recognized
+    fn assert_same_as_earley(grammar: &[&str], alphabet: &str, length: usize) -> usize {
+        let (rules, start) = (rules(grammar), Variable { symbol: 'E' });
+        let normal_form = ChomskyNormalForm::from_rules(&rules, start);
+        assert_shape(&normal_form);
+        let earley = Earley::new(&rules, start);
+        let mut sentences = vec![String::new()];
+        let mut last = sentences.clone();
+        for _ in 0..length {
+            last = last.iter()
+                .flat_map(|sentence| alphabet.chars().map(move |symbol| format!("{}{}", sentence, symbol)))
+                .collect();
+            sentences.extend(last.iter().cloned());
+        }
+        let mut recognized = 0;
+        for text in sentences {
+            let input = sentence(&text);
+            assert_eq!(normal_form.recognize(&input), earley.parse(&input).accepts(), "{:?}", text);
+            recognized += normal_form.recognize(&input) as usize;
+        }
+        recognized
+    }
+
+    #[test]
+    fn expression_grammar() {
+        // a, a+a, a*a, (a), a+a+a, ... up to five terminals
+        assert!(assert_same_as_earley(&["E:E+T", "E:T", "T:T*F", "T:F", "F:(E)", "F:a"], "a+*()", 5) > 5);
+    }
+
+    #[test]
+    fn empty_rules() {
+        let normal_form = ChomskyNormalForm::from_rules(&rules(&["E:AaA", "A:", "A:b"]), Variable { symbol: 'E' });
+        assert!(!normal_form.accept_empty());
+        // a, ba, ab and bab
+        assert_eq!(assert_same_as_earley(&["E:AaA", "A:", "A:b"], "ab", 4), 4);
+        // balanced parentheses, the empty one included
+        assert_eq!(assert_same_as_earley(&["E:EE", "E:(E)", "E:"], "()", 6), 9);
+        assert!(ChomskyNormalForm::from_rules(&rules(&["E:EE", "E:(E)", "E:"]), Variable { symbol: 'E' }).accept_empty());
+    }
+
+    #[test]
+    fn unit_cycles() {
+        // a followed by any number of b
+        assert_eq!(assert_same_as_earley(&["E:A", "A:B", "B:E", "B:a", "A:Ab"], "ab", 5), 5);
+    }
+}
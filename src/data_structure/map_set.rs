@@ -1,7 +1,7 @@
 use std::{collections::{HashSet, HashMap}, hash::Hash, ops::Deref};
 use std::marker::PhantomData;
 use std::ops::BitOr;
-use crate::data_structure::joinable::JoinAble;
+use crate::data_structure::JoinAble;
 
 #[derive(Debug, Clone)]
 pub struct MapSet<Key, Value, T: JoinAble = HashSet<Value>> {
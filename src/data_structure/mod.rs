@@ -1,3 +1,4 @@
 pub use joinable::JoinAble;
 mod joinable;
 pub mod map_set;
+pub mod bit_set;
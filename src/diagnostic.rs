@@ -0,0 +1,275 @@
+use std::fmt::Write;
+
+use crate::lexer::{DefinitionError, LexError, Location, Span};
+use crate::parsing::SyntaxError;
+use crate::parsing_table::{Action, Conflict, IStateMachine};
+use crate::syntax::{MixedChar, Rule, Terminal};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Style {
+    Plain,
+    Ansi,
+}
+
+impl Style {
+    /// Ansi when the program was started with `--color`
+    pub fn from_args() -> Self {
+        match std::env::args().any(|arg| arg == "--color") {
+            true => Self::Ansi,
+            false => Self::Plain,
+        }
+    }
+
+    pub fn paint(&self, code: &str, text: &str) -> String {
+        match self {
+            Self::Plain => text.to_string(),
+            Self::Ansi => format!("\x1b[{}m{}\x1b[0m", code, text),
+        }
+    }
+}
+
+const BOLD_RED: &str = "1;31";
+const BOLD_YELLOW: &str = "1;33";
+const BOLD_BLUE: &str = "1;34";
+const BOLD: &str = "1";
+
+/// A message about a place of a source, rendered with the line it points at
+/// underlined and the notes below it
+#[derive(Clone, Debug)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    /// name of the source shown before the line and column
+    pub origin: String,
+    pub span: Option<Span>,
+    pub notes: Vec<String>,
+}
+
+/// `a`, `b` or `c`, every terminal quoted
+fn one_of(terminals: &[Terminal]) -> String {
+    let quoted: Vec<String> = terminals.iter()
+        .map(|terminal| match *terminal == Terminal::end() {
+            true => "end of input".to_string(),
+            false => format!("`{}`", terminal),
+        })
+        .collect();
+    match quoted.split_last() {
+        None => String::new(),
+        Some((last, [])) => last.clone(),
+        Some((last, rest)) => format!("{} or {}", rest.join(", "), last),
+    }
+}
+
+/// Whether an input starting with `prefix` has an action in `state`, the prefix is completed
+/// with every string of `terminals` up to the lookahead of the machine
+fn has_action<'a, Machine>(machine: &Machine, state: usize, prefix: &mut Vec<Terminal>, terminals: &[Terminal]) -> bool
+    where Machine: IStateMachine<'a> {
+    if prefix.len() >= machine.lookahead() || prefix.last() == Some(&Terminal::end()) {
+        return machine.lookahead_action(state, prefix) != Action::Reject;
+    }
+    terminals.iter().any(|terminal| {
+        prefix.push(*terminal);
+        let found = has_action(machine, state, prefix, terminals);
+        prefix.pop();
+        found
+    })
+}
+
+/// The rule quoted, without the padding of its display
+fn quote(rule: &Rule) -> String {
+    format!("`{}`", rule.to_string().trim_end())
+}
+
+/// Line of the grammar file holding `rule`, as a span over the whole line
+fn rule_span(source: &str, rule: &Rule) -> Option<Span> {
+    let mut start = Location::default();
+    for line in source.split_inclusive('\n') {
+        let text = line.trim_end_matches(['\n', '\r']);
+        let end = text.chars().fold(start, |location, symbol| location.advance(symbol));
+        if Rule::try_from(text).is_ok_and(|found| found == *rule) {
+            return Some(Span { start, end });
+        }
+        start = line.chars().fold(start, |location, symbol| location.advance(symbol));
+    }
+    None
+}
+
+impl Diagnostic {
+    pub fn new(severity: Severity, message: impl Into<String>, origin: &str, span: Option<Span>) -> Self {
+        Self { severity, message: message.into(), origin: origin.to_string(), span, notes: vec![] }
+    }
+
+    pub fn note(mut self, note: impl Into<String>) -> Self {
+        self.notes.push(note.into());
+        self
+    }
+
+    /// The error with the terminals the state has an action for
+    pub fn syntax<'a, Machine>(error: &SyntaxError, machine: &Machine, rules: &[Rule], origin: &str) -> Self
+        where Machine: IStateMachine<'a> {
+        let message = match error.found == Terminal::end() {
+            true => "unexpected end of input".to_string(),
+            false => format!("unexpected `{}`", error.found),
+        };
+        let mut terminals: Vec<Terminal> = rules.iter()
+            .flat_map(|rule| rule.output.data.iter())
+            .filter_map(|symbol| match symbol {
+                MixedChar::Terminal(terminal) => Some(*terminal),
+                MixedChar::Variable(_) => None,
+            })
+            .chain([Terminal::end()])
+            .collect();
+        terminals.sort();
+        terminals.dedup();
+        let alphabet = terminals.clone();
+        terminals.retain(|terminal| has_action(machine, error.state, &mut vec![*terminal], &alphabet));
+        let diagnostic = Self::new(Severity::Error, message, origin, Some(error.span));
+        match terminals.is_empty() {
+            true => diagnostic,
+            false => diagnostic.note(format!("expected {}{} in state {}", if terminals.len() > 1 { "one of " } else { "" }, one_of(&terminals), error.state)),
+        }
+    }
+
+    pub fn lex(error: &LexError, origin: &str) -> Self {
+        let end = error.location.advance(error.symbol);
+        Self::new(Severity::Error, format!("no token starts with {:?}", error.symbol), origin, Some(Span { start: error.location, end }))
+    }
+
+    /// Points at the column of the bad `%` line of the grammar file
+    pub fn definition(error: &DefinitionError, source: &str, origin: &str) -> Self {
+        let text = source.lines().nth(error.line - 1).unwrap_or_default();
+        let offset: usize = source.split_inclusive('\n').take(error.line - 1).map(str::len).sum();
+        let before: usize = text.chars().take(error.column).map(char::len_utf8).sum();
+        let start = Location { offset: offset + before, line: error.line, column: error.column + 1 };
+        let end = text.chars().nth(error.column).map_or(start, |symbol| start.advance(symbol));
+        Self::new(Severity::Error, format!("bad token definition, {}", error.message), origin, Some(Span { start, end }))
+    }
+
+    /// Warning pointing at the rule of the grammar file whose entry was dropped
+    pub fn conflict(conflict: &Conflict, source: &str, origin: &str) -> Self {
+        match conflict {
+            Conflict::ShiftReduce { state, terminal, rule } => {
+                Self::new(Severity::Warning, format!("shift-reduce conflict in state {} on `{}`", state, terminal), origin, rule_span(source, rule))
+                    .note(format!("reducing {} is dropped, `{}` is shifted", quote(rule), terminal))
+            }
+            Conflict::ReduceReduce { state, terminal, kept, dropped } => {
+                let on = terminal.map_or(String::new(), |terminal| format!(" on `{}`", terminal));
+                let kept_line = rule_span(source, kept).map_or(String::new(), |span| format!(" from line {}", span.start.line));
+                Self::new(Severity::Warning, format!("reduce-reduce conflict in state {}{}", state, on), origin, rule_span(source, dropped))
+                    .note(format!("reducing {} is dropped, {}{} is reduced instead", quote(dropped), quote(kept), kept_line))
+            }
+        }
+    }
+
+    /// The message, the line of `source` the span starts on with the span underlined and the notes
+    pub fn render(&self, source: &str, style: Style) -> String {
+        let (label, color) = match self.severity {
+            Severity::Error => ("error", BOLD_RED),
+            Severity::Warning => ("warning", BOLD_YELLOW),
+        };
+        let mut output = String::new();
+        writeln!(output, "{}{}", style.paint(color, label), style.paint(BOLD, &format!(": {}", self.message))).unwrap();
+        let Some(span) = self.span else {
+            writeln!(output, "{} {}", style.paint(BOLD_BLUE, "-->"), self.origin).unwrap();
+            for note in &self.notes {
+                writeln!(output, "{} note: {}", style.paint(BOLD_BLUE, "="), note).unwrap();
+            }
+            return output;
+        };
+        let number = span.start.line.to_string();
+        let gutter = " ".repeat(number.len());
+        let text = source.lines().nth(span.start.line - 1).unwrap_or_default();
+        // a span over several lines is underlined up to the end of its first line
+        let end_column = match span.end.line == span.start.line {
+            true => span.end.column,
+            false => text.chars().count() + 1,
+        };
+        let width = end_column.saturating_sub(span.start.column).max(1);
+        writeln!(output, "{}{} {}:{}", gutter, style.paint(BOLD_BLUE, "-->"), self.origin, span.start).unwrap();
+        writeln!(output, "{} {}", gutter, style.paint(BOLD_BLUE, "|")).unwrap();
+        writeln!(output, "{} {}", style.paint(BOLD_BLUE, &format!("{} |", number)), text).unwrap();
+        writeln!(output, "{} {} {}{}", gutter, style.paint(BOLD_BLUE, "|"), " ".repeat(span.start.column - 1), style.paint(color, &"^".repeat(width))).unwrap();
+        for note in &self.notes {
+            writeln!(output, "{} {} note: {}", gutter, style.paint(BOLD_BLUE, "="), note).unwrap();
+        }
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Diagnostic, Style};
+    use crate::lexer::Lexer;
+    use crate::parsing::tests::{lr_k, lr_one, process};
+    use crate::parsing::ParsingProcess;
+    use crate::parsing_table::IStateMachine;
+    use crate::syntax::tests::rules;
+
+    const EXPRESSION: [&str; 4] = ["E:E+T", "E:T", "T:i", "T:(E)"];
+    const DEFINITIONS: &str = "%skip [ \\n]+\n%i [a-z]+";
+
+    #[test]
+    fn expected_terminals_with_two_lookahead() {
+        let grammar = ["E:Aac", "E:Bad", "A:b", "B:b"];
+        let machine = lr_k(&grammar, 2);
+        let error = process("bae").parse(&machine).unwrap_err();
+        // no action starts with a alone, the whole pair has to be probed
+        let diagnostic = Diagnostic::syntax(&error, &machine, &rules(&grammar), "input");
+        assert_eq!(diagnostic.notes, [format!("expected `a` in state {}", error.state)]);
+    }
+
+    #[test]
+    fn syntax_error_in_the_middle_of_a_line() {
+        let rules = rules(&EXPRESSION);
+        let input = "a + b\n+ (c d) + e";
+        let tokens = Lexer::from_source(DEFINITIONS, &rules).unwrap().unwrap().tokens(input).unwrap();
+        let machine = lr_one(&EXPRESSION);
+        let error = ParsingProcess::from_tokens(&tokens).parse(&machine).unwrap_err();
+        let rendered = Diagnostic::syntax(&error, &machine, &rules, "input").render(input, Style::Plain);
+        assert_eq!(rendered, "\
+error: unexpected `i`
+ --> input:2:6
+  |
+2 | + (c d) + e
+  |      ^
+  = note: expected one of `)` or `+` in state 6
+");
+    }
+
+    #[test]
+    fn bad_definition_in_the_grammar() {
+        let source = "E:E+T\n%i [a-z\nE:T";
+        let Some(Err(error)) = Lexer::from_source(source, &[]) else {
+            panic!("the definition is read");
+        };
+        let rendered = Diagnostic::definition(&error, source, "rule.txt").render(source, Style::Plain);
+        assert_eq!(rendered, "\
+error: bad token definition, unclosed [
+ --> rule.txt:2:4
+  |
+2 | %i [a-z
+  |    ^
+");
+    }
+
+    #[test]
+    fn conflict_in_the_grammar() {
+        let source = "E:E+E\nE:i";
+        let machine = lr_one(&["E:E+E", "E:i"]);
+        let rendered = Diagnostic::conflict(&machine.conflicts()[0], source, "rule.txt").render(source, Style::Plain);
+        assert_eq!(rendered, "\
+warning: shift-reduce conflict in state 4 on `+`
+ --> rule.txt:1:1
+  |
+1 | E:E+E
+  | ^^^^^
+  = note: reducing `E->E + E` is dropped, `+` is shifted
+");
+    }
+}
@@ -0,0 +1,101 @@
+use prettytable::Table;
+
+use crate::diagnostic::Style;
+use crate::parsing::{self, ParsingProcess};
+use crate::parsing_table::IStateMachine;
+use crate::syntax::{self, TerminalString};
+
+/// Steps after which a parse counts as rejected, a broken table can reduce forever
+pub const MAX_STEPS: usize = 10_000;
+
+pub struct Trace {
+    pub accepted: bool,
+    pub table: Table,
+}
+
+/// Runs `input` through the machine and keeps every step
+pub fn trace<Machine>(machine: &Machine, input: &TerminalString) -> Trace
+    where Machine: for<'a> IStateMachine<'a> {
+    let mut input_vec: Vec<char> = input.data.iter().map(|terminal| terminal.symbol).collect();
+    input_vec.push(syntax::END_TERMINAL);
+    let mut history = vec![ParsingProcess::new(&input_vec)];
+    while let Some(next) = history.last().unwrap().get_next(machine) {
+        if history.len() >= MAX_STEPS {
+            break;
+        }
+        history.push(next);
+    }
+    let accepted = history.len() < MAX_STEPS && history.last().unwrap().clone().run(machine) == Some(true);
+    let table = parsing::history_table(machine, &history, Style::Plain);
+    Trace { accepted, table }
+}
+
+/// Machine taking part in the comparison
+pub struct Candidate<'a> {
+    pub name: &'static str,
+    pub run: Box<dyn Fn(&TerminalString) -> Trace + 'a>,
+}
+
+impl<'a> Candidate<'a> {
+    pub fn new<Machine>(name: &'static str, machine: &'a Machine) -> Self
+        where Machine: for<'b> IStateMachine<'b> {
+        Self { name, run: Box::new(move |input| trace(machine, input)) }
+    }
+}
+
+pub struct Disagreement {
+    pub input: TerminalString,
+    pub left: (&'static str, Trace),
+    pub right: (&'static str, Trace),
+}
+
+/// Every input on which two candidates disagree, shortest input first
+pub fn compare(candidates: &[Candidate], inputs: &[TerminalString]) -> Vec<Disagreement> {
+    let mut inputs: Vec<&TerminalString> = inputs.iter().collect();
+    inputs.sort_by_key(|input| input.data.len());
+    let mut output = vec![];
+    for input in inputs {
+        let mut traces = candidates.iter().map(|candidate| (candidate.name, (candidate.run)(input)));
+        let Some(first) = traces.next() else {
+            continue;
+        };
+        if let Some(other) = traces.find(|(_, trace)| trace.accepted != first.1.accepted) {
+            output.push(Disagreement { input: input.clone(), left: first, right: other });
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{compare, Candidate};
+    use crate::parsing::tests::{lr_one, lr_zero};
+    use crate::parsing_table::IStateMachine;
+    use crate::syntax::tests::sentence;
+
+    // reduce-reduce conflict after c, LR(0) keeps one of the rules whatever follows
+    const REDUCE_REDUCE: [&str; 5] = ["E:Aa", "E:Bbb", "E:Bb", "A:c", "B:c"];
+
+    #[test]
+    fn shortest_disagreement_of_a_broken_machine() {
+        let (broken, reference) = (lr_zero(&REDUCE_REDUCE), lr_one(&REDUCE_REDUCE));
+        assert!(!broken.conflicts().is_empty());
+        assert!(reference.conflicts().is_empty());
+        let candidates = [Candidate::new("lr(0)", &broken), Candidate::new("lr(1)", &reference)];
+        let inputs = ["cab", "c", "cbb", "ca", "cb"].map(sentence);
+        let disagreements = compare(&candidates, &inputs);
+        let found: Vec<String> = disagreements.iter().map(|disagreement| disagreement.input.to_string()).collect();
+        assert_eq!(found, ["cb", "cbb"]);
+        let shortest = &disagreements[0];
+        assert_eq!((shortest.left.0, shortest.right.0), ("lr(0)", "lr(1)"));
+        assert_ne!(shortest.left.1.accepted, shortest.right.1.accepted);
+    }
+
+    #[test]
+    fn agreeing_machines() {
+        let grammar = ["E:E+T", "E:T", "T:a"];
+        let (lr_zero, lr_one) = (lr_zero(&grammar), lr_one(&grammar));
+        let candidates = [Candidate::new("lr(0)", &lr_zero), Candidate::new("lr(1)", &lr_one)];
+        assert!(compare(&candidates, &["", "a", "a+", "a+a", "aa"].map(sentence)).is_empty());
+    }
+}
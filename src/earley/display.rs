@@ -0,0 +1,72 @@
+use std::fmt::{Display, Formatter};
+
+use prettytable::{Cell, Row, Table};
+
+use crate::earley::{Chart, EarleyItem, Operation};
+use crate::itemset::DOT;
+use crate::syntax::Rule;
+
+pub struct ItemDisplay<'a> {
+    item: &'a EarleyItem,
+    rules: &'a [Rule],
+}
+
+impl EarleyItem {
+    pub fn display<'a>(&'a self, rules: &'a [Rule]) -> ItemDisplay<'a> {
+        ItemDisplay { item: self, rules }
+    }
+}
+
+impl Display for ItemDisplay<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let rule = &self.rules[self.item.rule];
+        write!(f, "[{} ->", rule.clause)?;
+        for (i, character) in rule.output.data.iter().enumerate() {
+            if i == self.item.dot {
+                write!(f, " {}", DOT)?;
+            }
+            write!(f, " {}", character)?;
+        }
+        if self.item.dot == rule.len() {
+            write!(f, " {}", DOT)?;
+        }
+        write!(f, ", {}]", self.item.origin)
+    }
+}
+
+impl Display for Operation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Operation::Start => "Start",
+            Operation::Predict => "Predict",
+            Operation::Scan => "Scan",
+            Operation::Complete => "Complete",
+            Operation::NullableSkip => "Nullable",
+        };
+        f.pad(name)
+    }
+}
+
+impl Chart<'_> {
+    /// Trace of the chart with every set up to `last_set`, in the same layout as the LR trace
+    pub fn table(&self, last_set: usize) -> Table {
+        let mut table = Table::new();
+        table.add_row(Row::new(vec![
+            Cell::new("Set"),
+            Cell::new("Item"),
+            Cell::new("Operation"),
+            Cell::new("Input"),
+        ]));
+        for (position, set) in self.sets.iter().enumerate().take(last_set + 1) {
+            for (item, operation) in &set.items {
+                table.add_row(Row::new(vec![
+                    Cell::new(&position.to_string()),
+                    Cell::new(&item.display(&self.earley.rules).to_string()),
+                    Cell::new(&operation.to_string()),
+                    Cell::new(&format!("{:?}", &self.input[position..])),
+                ]));
+            }
+        }
+        table
+    }
+}
@@ -0,0 +1,248 @@
+mod display;
+
+use std::collections::HashSet;
+
+use crate::first_follow;
+use crate::forest::{Family, Forest};
+use crate::syntax::{MixedChar, Rule, Terminal, TerminalString, Variable};
+
+/// Earley's algorithm, with the nullable completion of Aycock and Horspool
+/// https://doi.org/10.1145/362007.362035
+pub struct Earley {
+    rules: Vec<Rule>,
+    start: Variable,
+    nullable: HashSet<Variable>,
+}
+
+#[derive(Hash, PartialEq, Eq, Clone, Copy, Debug)]
+pub struct EarleyItem {
+    pub rule: usize,
+    pub dot: usize,
+    pub origin: usize,
+}
+
+/// How an item got into its set, shown while stepping through the chart
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Operation {
+    Start,
+    Predict,
+    Scan,
+    Complete,
+    NullableSkip,
+}
+
+#[derive(Default)]
+pub struct EarleySet {
+    pub items: Vec<(EarleyItem, Operation)>,
+    seen: HashSet<EarleyItem>,
+}
+
+impl EarleySet {
+    fn add(&mut self, item: EarleyItem, operation: Operation) {
+        if self.seen.insert(item) {
+            self.items.push((item, operation));
+        }
+    }
+}
+
+pub struct Chart<'earley> {
+    earley: &'earley Earley,
+    pub input: Vec<Terminal>,
+    pub sets: Vec<EarleySet>,
+}
+
+impl EarleyItem {
+    fn advance(&self) -> Self {
+        Self { dot: self.dot + 1, ..*self }
+    }
+    pub fn symbol(&self, rules: &[Rule]) -> Option<MixedChar> {
+        rules[self.rule].output.data.get(self.dot).copied()
+    }
+}
+
+impl Earley {
+    pub fn new(rules: &[Rule], start: Variable) -> Self {
+        Self { rules: rules.to_vec(), start, nullable: first_follow::nullable(rules) }
+    }
+
+    pub fn rules(&self) -> &[Rule] {
+        &self.rules
+    }
+
+    fn rules_of(&self, variable: Variable) -> impl Iterator<Item = usize> + '_ {
+        self.rules.iter().enumerate().filter(move |(_, rule)| rule.clause == variable).map(|(id, _)| id)
+    }
+
+    pub fn parse(&self, input: &TerminalString) -> Chart<'_> {
+        let input = input.without_end().to_vec();
+        let mut sets: Vec<EarleySet> = (0..=input.len()).map(|_| EarleySet::default()).collect();
+        for rule in self.rules_of(self.start) {
+            sets[0].add(EarleyItem { rule, dot: 0, origin: 0 }, Operation::Start);
+        }
+        for position in 0..=input.len() {
+            let mut index = 0;
+            while let Some((item, _)) = sets[position].items.get(index).copied() {
+                index += 1;
+                match item.symbol(&self.rules) {
+                    None => {
+                        let clause = self.rules[item.rule].clause;
+                        let waiting: Vec<EarleyItem> = sets[item.origin].items.iter()
+                            .map(|(waiting, _)| *waiting)
+                            .filter(|waiting| waiting.symbol(&self.rules) == Some(clause.into()))
+                            .collect();
+                        for waiting in waiting {
+                            sets[position].add(waiting.advance(), Operation::Complete);
+                        }
+                    }
+                    Some(MixedChar::Variable(variable)) => {
+                        for rule in self.rules_of(variable) {
+                            sets[position].add(EarleyItem { rule, dot: 0, origin: position }, Operation::Predict);
+                        }
+                        if self.nullable.contains(&variable) {
+                            sets[position].add(item.advance(), Operation::NullableSkip);
+                        }
+                    }
+                    Some(MixedChar::Terminal(terminal)) => {
+                        if input.get(position) == Some(&terminal) {
+                            sets[position + 1].add(item.advance(), Operation::Scan);
+                        }
+                    }
+                }
+            }
+        }
+        Chart { earley: self, input, sets }
+    }
+}
+
+impl Chart<'_> {
+    fn is_complete(&self, item: &EarleyItem) -> bool {
+        self.earley.rules[item.rule].len() == item.dot
+    }
+
+    pub fn accepts(&self) -> bool {
+        self.sets[self.input.len()].items.iter().any(|(item, _)| {
+            item.origin == 0 && self.is_complete(item) && self.earley.rules[item.rule].clause == self.earley.start
+        })
+    }
+
+    /// Shared packed parse forest of every derivation of the input, `None` when the input is rejected
+    pub fn forest(&self) -> Option<Forest> {
+        if !self.accepts() {
+            return None;
+        }
+        let mut completed: HashSet<(usize, usize, usize)> = HashSet::new();
+        for (end, set) in self.sets.iter().enumerate() {
+            for (item, _) in &set.items {
+                if self.is_complete(item) {
+                    completed.insert((item.rule, item.origin, end));
+                }
+            }
+        }
+        let mut forest = Forest::new(&self.earley.rules);
+        let root = self.build(&mut forest, &completed, self.earley.start, 0, self.input.len());
+        forest.root = Some(root);
+        Some(forest)
+    }
+
+    fn build(&self, forest: &mut Forest, completed: &HashSet<(usize, usize, usize)>, variable: Variable, start: usize, end: usize) -> usize {
+        let (node, created) = forest.node(variable.into(), start, end);
+        if !created {
+            return node;
+        }
+        for rule in self.earley.rules_of(variable) {
+            if !completed.contains(&(rule, start, end)) {
+                continue;
+            }
+            for children in self.decompose(forest, completed, rule, 0, start, end) {
+                forest.add_family(node, Family { rule, children });
+            }
+        }
+        node
+    }
+
+    /// Every way the symbols of `rule` from `index` onward can cover `position..end`
+    fn decompose(&self, forest: &mut Forest, completed: &HashSet<(usize, usize, usize)>, rule: usize, index: usize, position: usize, end: usize) -> Vec<Vec<usize>> {
+        let Some(symbol) = self.earley.rules[rule].output.data.get(index).copied() else {
+            return match position == end {
+                true => vec![vec![]],
+                false => vec![],
+            };
+        };
+        let mut output = vec![];
+        match symbol {
+            MixedChar::Terminal(terminal) => {
+                if position < end && self.input[position] == terminal {
+                    let (child, _) = forest.node(symbol, position, position + 1);
+                    for mut rest in self.decompose(forest, completed, rule, index + 1, position + 1, end) {
+                        rest.insert(0, child);
+                        output.push(rest);
+                    }
+                }
+            }
+            MixedChar::Variable(variable) => {
+                for middle in position..=end {
+                    let derivable = self.earley.rules_of(variable).any(|id| completed.contains(&(id, position, middle)));
+                    if !derivable {
+                        continue;
+                    }
+                    let rests = self.decompose(forest, completed, rule, index + 1, middle, end);
+                    if rests.is_empty() {
+                        continue;
+                    }
+                    let child = self.build(forest, completed, variable, position, middle);
+                    for mut rest in rests {
+                        rest.insert(0, child);
+                        output.push(rest);
+                    }
+                }
+            }
+        }
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Earley;
+    use crate::syntax::tests::{rules, sentence};
+    use crate::syntax::Variable;
+
+    fn earley(grammar: &[&str], start: char) -> Earley {
+        Earley::new(&rules(grammar), Variable { symbol: start })
+    }
+
+    #[test]
+    fn nullable_variables_before_a_terminal() {
+        let parser = earley(&["E:AAx", "A:"], 'E');
+        assert!(parser.parse(&sentence("x")).accepts());
+        assert!(!parser.parse(&sentence("")).accepts());
+        assert!(!parser.parse(&sentence("xx")).accepts());
+    }
+
+    #[test]
+    fn variable_nullable_through_another() {
+        // B is only nullable through A, which is completed in the same set B is predicted in
+        let parser = earley(&["S:ABA", "A:", "A:a", "B:A", "B:b"], 'S');
+        for (text, accepted) in [("", true), ("a", true), ("aa", true), ("aaa", true), ("b", true), ("aba", true), ("bb", false), ("aaaa", false)] {
+            assert_eq!(parser.parse(&sentence(text)).accepts(), accepted, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn forest_of_an_ambiguous_sentence() {
+        let parser = earley(&["E:E+E", "E:i"], 'E');
+        let forest = parser.parse(&sentence("i+i+i")).forest().unwrap();
+        assert!(forest.is_ambiguous());
+        let trees: Vec<String> = forest.trees(10).iter().map(ToString::to_string).collect();
+        assert_eq!(trees, ["E(E(i) + E(E(i) + E(i)))", "E(E(E(i) + E(i)) + E(i))"]);
+        assert_eq!(forest.to_string(), "\
+E[0..5] -> E[0..1] +[1..2] E[2..5]
+        | E[0..3] +[3..4] E[4..5]
+E[0..1] -> i[0..1]
+E[2..5] -> E[2..3] +[3..4] E[4..5]
+E[2..3] -> i[2..3]
+E[4..5] -> i[4..5]
+E[0..3] -> E[0..1] +[1..2] E[2..3]
+");
+    }
+}
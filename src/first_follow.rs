@@ -1,29 +1,92 @@
-use crate::{syntax::{Rule, Terminal, Variable}};
+use crate::{syntax::{MixedChar, Rule, Terminal, Variable}};
 
-use std::collections::{HashSet as Set};
+use std::collections::{BTreeSet, HashMap, HashSet as Set};
 
-use std::iter;
-use once_cell::sync::Lazy;
 use crate::data_structure::map_set::MapSet;
-use crate::data_structure::JoinAble;
 
-static DEFAULT_MAPSET:Lazy<MapSet<Variable, Terminal>> = Lazy::new(||MapSet::default());
-
-fn track_adding<T, Key, Value>(current_val: &Key, track: &MapSet<Key, Key>, visited: &mut Set<Key>, map: &mut MapSet<Key, Value, T>)
-where T: JoinAble + Clone,
-      Key: Copy + std::hash::Hash + std::cmp::Eq,
-      Value: std::hash::Hash+ Eq + Clone{
-    if visited.contains(current_val) {
-        return;
+/// Every variable which can derive the empty string
+pub fn nullable(rules: &[Rule]) -> Set<Variable> {
+    let mut nullable = Set::new();
+    loop {
+        let before = nullable.len();
+        for rule in rules {
+            let all_nullable = rule.output.data.iter().all(|symbol| match symbol {
+                MixedChar::Variable(variable) => nullable.contains(variable),
+                MixedChar::Terminal(_) => false,
+            });
+            if all_nullable {
+                nullable.insert(rule.clause);
+            }
+        }
+        if nullable.len() == before {
+            return nullable;
+        }
     }
-    visited.insert(*current_val);
-    for next_variable in track.get(current_val) {
-        track_adding(next_variable, track, visited, map);
-        map.join(*current_val,* next_variable);
+}
+
+/// Strings of `left` followed by strings of `right`, cut to `k` terminals
+fn concat_k(left: &BTreeSet<Vec<Terminal>>, right: &BTreeSet<Vec<Terminal>>, k: usize) -> BTreeSet<Vec<Terminal>> {
+    let mut output = BTreeSet::new();
+    for prefix in left {
+        if prefix.len() >= k {
+            output.insert(prefix[..k].to_vec());
+            continue;
+        }
+        for suffix in right {
+            let mut string = prefix.clone();
+            string.extend(suffix.iter().take(k - prefix.len()));
+            output.insert(string);
+        }
     }
-    
+    output
 }
 
+/// FIRST_k, every prefix of at most k terminals a variable derives,
+/// a string shorter than k means the derivation ends there
+pub struct FirstK {
+    k: usize,
+    map: HashMap<Variable, BTreeSet<Vec<Terminal>>>,
+}
+
+impl FirstK {
+    pub fn from_rule(rules: &[Rule], k: usize) -> Self {
+        let mut first = Self { k, map: HashMap::new() };
+        loop {
+            let mut changed = false;
+            for rule in rules {
+                let strings = first.of(&rule.output.data, &[]);
+                let entry = first.map.entry(rule.clause).or_default();
+                for string in strings {
+                    changed |= entry.insert(string);
+                }
+            }
+            if !changed {
+                return first;
+            }
+        }
+    }
+
+    pub fn k(&self) -> usize {
+        self.k
+    }
+
+    pub fn get(&self, variable: &Variable) -> Option<&BTreeSet<Vec<Terminal>>> {
+        self.map.get(variable)
+    }
+
+    /// FIRST_k of `sequence` followed by the lookahead `follow`
+    pub fn of(&self, sequence: &[MixedChar], follow: &[Terminal]) -> BTreeSet<Vec<Terminal>> {
+        let empty = BTreeSet::new();
+        let mut output = BTreeSet::from([vec![]]);
+        for symbol in sequence {
+            output = match symbol {
+                MixedChar::Terminal(terminal) => concat_k(&output, &BTreeSet::from([vec![*terminal]]), self.k),
+                MixedChar::Variable(variable) => concat_k(&output, self.map.get(variable).unwrap_or(&empty), self.k),
+            };
+        }
+        concat_k(&output, &BTreeSet::from([follow.to_vec()]), self.k)
+    }
+}
 
 #[derive(Default)]
 pub struct First{
@@ -31,22 +94,52 @@ pub struct First{
     empty: Set<Variable>
 }
 impl First {
+    /// Grown until a fixed point, a nullable prefix lets the next symbol add its first set too
     pub fn from_rule(rule:&[Rule]) -> Self {
         let mut map: MapSet<Variable, Terminal> = MapSet::default();
-        let mut track:MapSet<Variable, Variable> = MapSet::default();
-        let mut empty = Set::default();
-        for (variable, first) in rule.iter().map(|rule| (rule.clause, rule.output.data.first())) {
-            use crate::syntax::MixedChar::{Terminal, Variable};
-            match first {
-                Some(Terminal(next_ter)) => map.add(variable, *next_ter), // E -> aB
-                Some(Variable(next_var)) => track.add(variable, *next_var), // E -> B
-                None => { empty.insert(variable); }, // E -> Ɛ
+        let empty = nullable(rule);
+        loop {
+            let mut changed = false;
+            for rule in rule {
+                for symbol in &rule.output.data {
+                    let firsts = match symbol {
+                        MixedChar::Terminal(terminal) => Set::from([*terminal]),
+                        MixedChar::Variable(variable) => map.get(variable).clone(),
+                    };
+                    for terminal in firsts {
+                        changed |= !map.get(&rule.clause).contains(&terminal);
+                        map.add(rule.clause, terminal);
+                    }
+                    if !matches!(symbol, MixedChar::Variable(variable) if empty.contains(variable)) {
+                        break;
+                    }
+                }
+            }
+            if !changed {
+                return Self { map, empty };
             }
         }
-        for variable in track.keys() {
-            track_adding(variable, &track,&mut Set::new(), &mut map)
+    }
+
+    /// FIRST(β a), the terminals starting `sequence` followed by `follow`
+    pub fn of(&self, sequence: &[MixedChar], follow: Terminal) -> Set<Terminal> {
+        let mut output = Set::new();
+        for symbol in sequence {
+            match symbol {
+                MixedChar::Terminal(terminal) => {
+                    output.insert(*terminal);
+                    return output;
+                }
+                MixedChar::Variable(variable) => {
+                    output.extend(self.map.get(variable).iter().copied());
+                    if !self.empty.contains(variable) {
+                        return output;
+                    }
+                }
+            }
         }
-        Self { map, empty }
+        output.insert(follow);
+        output
     }
 
     pub fn print(&self) {
@@ -58,75 +151,32 @@ impl First {
     }
 }
 
-#[derive(Default)]
-pub struct Follow{
-    map: MapSet<Variable, Terminal, MapSet<usize, Terminal>>,
-    rule_track: MapSet<Variable, usize>,
-}
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
 
-impl Follow {
-    pub fn new(first_set: &First, rules: &[Rule]) -> Self{
-        let mut track: MapSet<Variable, Variable> = MapSet::default();
-        let mut rule_track: MapSet<Variable, usize> = MapSet::default();
-
-        let mut map: MapSet<Variable, Terminal, MapSet<usize, Terminal>> = MapSet::default();
-        map.add(Variable::accept(), (0, iter::once(Terminal::end()).collect()));
-        for (rule_id, rule) in rules.iter().enumerate(){
-            let clause = rule.clause;
-            for (id, variable) in rule.output.data.iter().enumerate().filter_map(|(id, char)| Some((id, char.try_into().ok()?))) {
-                let Some(next) = rule.output.data.get(id + 1) else {
-                    
-                    // A-> pB is a production
-                    if variable != clause {
-                        rule_track.add(variable, rule_id);
-                        track.add(variable, clause);
-                    }
-                    break;
-                };
-                // A-> pBq is a production
-                use crate::syntax::MixedChar::{Terminal, Variable};
-                match next {
-                    Terminal(next_ter) => map.add_once(variable, rule_id, next_ter.to_owned()),
-                    Variable(next_var) => {
-                        // if q -> Ɛ is a production act as if A->pB is also production
-                        if first_set.empty.contains(next_var) && variable != clause{
-                            rule_track.add(variable, rule_id);
-                            track.add(variable, clause);
-                        }
-                        // map.append(variable, first_set.map.get(next_var).to_owned())
-                        map.append(variable, MapSet::new(rule_id, first_set.map.get(next_var).to_owned()))
-                        // follows.append(first_set.map.get(next_var).into());
-                    },
-                }
-            }
-        }
-        for variable in track.keys() {
-            track_adding(variable, &track,&mut Set::new(), &mut map);
-            track_adding(variable, &track,&mut Set::new(), &mut rule_track);
-        }
+    use super::{concat_k, FirstK};
+    use crate::syntax::tests::rules;
+    use crate::syntax::{Terminal, Variable};
 
-        Self{map, rule_track}
+    fn strings(texts: &[&str]) -> BTreeSet<Vec<Terminal>> {
+        texts.iter().map(|text| text.chars().map(|symbol| Terminal { symbol }).collect()).collect()
     }
-    pub fn get(&self, key: &Variable) -> Set<Terminal> {
-        self.map.get(key).all().unwrap_or_default()
-    }
-    pub fn get_filtered<'a>(&self, key: &Variable,
-                            available_rule: impl IntoIterator<Item = &'a usize>,
-                            kernel_follow: impl IntoIterator<Item = (usize, Terminal)>) -> Set<Terminal> {
-        let mut first_val = self.map.get(key).aggregate(available_rule).unwrap_or_default(); // This is from A->aBc
-        let rules = self.rule_track.get(key);
-        for (rule_id, rule_follow) in kernel_follow{
-            if rules.contains(&rule_id) {
-                first_val.insert(rule_follow);
-            }
-        }
-        first_val
+
+    #[test]
+    fn concatenation_is_cut_to_k() {
+        let (left, right) = (strings(&["", "a", "ab", "abc"]), strings(&["", "x", "xy"]));
+        assert_eq!(concat_k(&left, &right, 2), strings(&["", "x", "xy", "a", "ax", "ab"]));
+        assert_eq!(concat_k(&left, &right, 3), strings(&["", "x", "xy", "a", "ax", "axy", "ab", "abx", "abc"]));
+        assert_eq!(concat_k(&left, &BTreeSet::new(), 2), strings(&["ab"]));
     }
 
-    pub fn print(&self) {
-        println!("Follow set");
-        for (var, terminals) in self.map.iter() {
-            println!("{} {:?}", var, terminals);
-        }
+    #[test]
+    fn first_k_of_a_grammar() {
+        let first = FirstK::from_rule(&rules(&["E:Aac", "E:Bad", "A:b", "B:", "B:c"]), 2);
+        assert_eq!(first.get(&Variable { symbol: 'E' }).unwrap(), &strings(&["ba", "ad", "ca"]));
+        assert_eq!(first.get(&Variable { symbol: 'B' }).unwrap(), &strings(&["", "c"]));
+        let sequence = rules(&["E:Bb"])[0].output.data.clone();
+        assert_eq!(first.of(&sequence, &[Terminal { symbol: 'z' }]), strings(&["bz", "cb"]));
     }
-}
\ No newline at end of file
+}
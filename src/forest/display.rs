@@ -0,0 +1,53 @@
+use std::fmt::{Display, Formatter};
+
+use crate::forest::{Forest, ForestNode, Tree};
+use crate::syntax::MixedChar;
+
+struct NodeName<'a>(&'a ForestNode);
+
+impl Display for NodeName<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}[{}..{}]", self.0.symbol, self.0.start, self.0.end)
+    }
+}
+
+impl Display for Forest {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for id in self.reachable() {
+            let node = self.get(id);
+            for (alternative, family) in node.families.iter().enumerate() {
+                match alternative {
+                    0 => write!(f, "{} ->", NodeName(node))?,
+                    _ => write!(f, "{:width$} |", "", width = NodeName(node).to_string().chars().count())?,
+                }
+                for child in &family.children {
+                    write!(f, " {}", NodeName(self.get(*child)))?;
+                }
+                if family.children.is_empty() {
+                    write!(f, " \u{03B5}")?;
+                }
+                writeln!(f)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Display for Tree {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if let MixedChar::Terminal(terminal) = self.symbol {
+            return write!(f, "{}", terminal);
+        }
+        write!(f, "{}(", self.symbol)?;
+        for (i, child) in self.children.iter().enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{}", child)?;
+        }
+        if self.children.is_empty() {
+            write!(f, "\u{03B5}")?;
+        }
+        write!(f, ")")
+    }
+}
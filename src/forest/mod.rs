@@ -0,0 +1,140 @@
+mod display;
+
+use std::collections::HashMap;
+
+use crate::syntax::{MixedChar, Rule};
+
+/// One way of deriving a node, the children are the ids of the nodes for each symbol of the rule
+#[derive(Hash, PartialEq, Eq, Clone, Debug)]
+pub struct Family {
+    pub rule: usize,
+    pub children: Vec<usize>,
+}
+
+/// A single parse tree taken out of a forest
+#[derive(Hash, PartialEq, Eq, Clone, Debug)]
+pub struct Tree {
+    pub symbol: MixedChar,
+    pub rule: Option<usize>,
+    pub children: Vec<Tree>,
+}
+
+#[derive(Clone, Debug)]
+pub struct ForestNode {
+    pub symbol: MixedChar,
+    pub start: usize,
+    pub end: usize,
+    pub families: Vec<Family>,
+}
+
+/// Shared packed parse forest, every (symbol, start, end) is stored once
+/// and a node with more than one family is an ambiguity
+#[derive(Clone, Debug)]
+pub struct Forest {
+    pub rules: Vec<Rule>,
+    nodes: Vec<ForestNode>,
+    index: HashMap<(MixedChar, usize, usize), usize>,
+    pub root: Option<usize>,
+}
+
+impl Forest {
+    pub fn new(rules: &[Rule]) -> Self {
+        Self { rules: rules.to_vec(), nodes: vec![], index: HashMap::new(), root: None }
+    }
+
+    /// Id of the node for `symbol` spanning `start..end`, the flag is set when the node was just created
+    pub fn node(&mut self, symbol: MixedChar, start: usize, end: usize) -> (usize, bool) {
+        if let Some(id) = self.index.get(&(symbol, start, end)) {
+            return (*id, false);
+        }
+        let id = self.nodes.len();
+        self.nodes.push(ForestNode { symbol, start, end, families: vec![] });
+        self.index.insert((symbol, start, end), id);
+        (id, true)
+    }
+
+    pub fn find(&self, symbol: MixedChar, start: usize, end: usize) -> Option<usize> {
+        self.index.get(&(symbol, start, end)).copied()
+    }
+
+    pub fn add_family(&mut self, node: usize, family: Family) {
+        if !self.nodes[node].families.contains(&family) {
+            self.nodes[node].families.push(family);
+        }
+    }
+
+    pub fn get(&self, node: usize) -> &ForestNode {
+        &self.nodes[node]
+    }
+
+    pub fn nodes(&self) -> &[ForestNode] {
+        &self.nodes
+    }
+
+    /// Nodes reachable from the root, in depth first order
+    pub fn reachable(&self) -> Vec<usize> {
+        let mut visited = vec![false; self.nodes.len()];
+        let mut output = vec![];
+        let mut stack: Vec<usize> = self.root.into_iter().collect();
+        while let Some(node) = stack.pop() {
+            if visited[node] {
+                continue;
+            }
+            visited[node] = true;
+            output.push(node);
+            for family in self.nodes[node].families.iter().rev() {
+                stack.extend(family.children.iter().rev());
+            }
+        }
+        output
+    }
+
+    pub fn is_ambiguous(&self) -> bool {
+        self.reachable().iter().any(|node| self.nodes[*node].families.len() > 1)
+    }
+
+    /// Up to `limit` distinct trees of the forest, a cycle is unrolled at most once
+    /// so a cyclic grammar still shows two different trees
+    pub fn trees(&self, limit: usize) -> Vec<Tree> {
+        let Some(root) = self.root else {
+            return vec![];
+        };
+        self.trees_of(root, limit, &mut vec![])
+    }
+
+    fn trees_of(&self, node: usize, limit: usize, ancestors: &mut Vec<usize>) -> Vec<Tree> {
+        let symbol = self.nodes[node].symbol;
+        if self.nodes[node].families.is_empty() {
+            return match symbol {
+                MixedChar::Terminal(_) => vec![Tree { symbol, rule: None, children: vec![] }],
+                MixedChar::Variable(_) => vec![],
+            };
+        }
+        if ancestors.iter().filter(|ancestor| **ancestor == node).count() >= 2 {
+            return vec![];
+        }
+        ancestors.push(node);
+        let mut output = vec![];
+        for family in &self.nodes[node].families {
+            let mut partial: Vec<Vec<Tree>> = vec![vec![]];
+            for child in &family.children {
+                let child_trees = self.trees_of(*child, limit, ancestors);
+                partial = partial.iter()
+                    .flat_map(|prefix| child_trees.iter().map(move |tree| {
+                        let mut next = prefix.clone();
+                        next.push(tree.clone());
+                        next
+                    }))
+                    .take(limit)
+                    .collect();
+            }
+            for children in partial {
+                if output.len() < limit {
+                    output.push(Tree { symbol, rule: Some(family.rule), children });
+                }
+            }
+        }
+        ancestors.pop();
+        output
+    }
+}
@@ -0,0 +1,427 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fs;
+use std::io;
+
+use crate::earley::Earley;
+use crate::syntax::{MixedChar, Rule, Terminal, TerminalString, Variable};
+
+/// xorshift64* generator, enough for picking productions
+/// https://doi.org/10.18637/jss.v008.i14
+pub struct Random {
+    state: u64,
+}
+
+impl Random {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed.max(1) }
+    }
+
+    pub fn from_time() -> Self {
+        let nanos = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map(|time| time.as_nanos()).unwrap_or(1);
+        Self::new(nanos as u64)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545F4914F6CDD1D)
+    }
+
+    pub fn below(&mut self, bound: usize) -> usize {
+        (self.next_u64() % bound as u64) as usize
+    }
+}
+
+/// Builds sentences of the grammar, every rule knows the height of its smallest derivation tree
+/// so a derivation can always be steered back to terminals
+pub struct SentenceGenerator<'rules> {
+    rules: &'rules [Rule],
+    start: Variable,
+    rule_height: Vec<Option<usize>>,
+    variable_height: HashMap<Variable, usize>,
+}
+
+impl<'rules> SentenceGenerator<'rules> {
+    pub fn new(rules: &'rules [Rule], start: Variable) -> Self {
+        let mut rule_height = vec![None; rules.len()];
+        let mut variable_height: HashMap<Variable, usize> = HashMap::new();
+        loop {
+            let mut changed = false;
+            for (id, rule) in rules.iter().enumerate() {
+                let children: Option<Vec<usize>> = rule.output.data.iter().map(|symbol| match symbol {
+                    MixedChar::Terminal(_) => Some(0),
+                    MixedChar::Variable(variable) => variable_height.get(variable).copied(),
+                }).collect();
+                let Some(height) = children.map(|children| children.into_iter().max().unwrap_or(0) + 1) else {
+                    continue;
+                };
+                if rule_height[id].is_none_or(|current| height < current) {
+                    rule_height[id] = Some(height);
+                    changed = true;
+                }
+                let best = variable_height.entry(rule.clause).or_insert(height);
+                if height < *best {
+                    *best = height;
+                    changed = true;
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+        Self { rules, start, rule_height, variable_height }
+    }
+
+    fn rules_of(&self, variable: Variable) -> impl Iterator<Item = usize> + '_ {
+        self.rules.iter().enumerate()
+            .filter(move |(id, rule)| rule.clause == variable && self.rule_height[*id].is_some())
+            .map(|(id, _)| id)
+    }
+
+    /// Rule of `variable` with the smallest derivation tree
+    fn shortest_rule(&self, variable: Variable) -> Option<usize> {
+        self.rules_of(variable).min_by_key(|id| self.rule_height[*id])
+    }
+
+    /// Random derivation, any rule may be picked until `max_depth` after which only the shortest rules are used
+    pub fn random(&self, random: &mut Random, max_depth: usize) -> Option<TerminalString> {
+        self.variable_height.get(&self.start)?;
+        let mut output = TerminalString::new();
+        self.expand_random(random, self.start, 0, max_depth, &mut output);
+        Some(output)
+    }
+
+    fn expand_random(&self, random: &mut Random, variable: Variable, depth: usize, max_depth: usize, output: &mut TerminalString) {
+        let candidates: Vec<usize> = self.rules_of(variable).collect();
+        let rule = match depth < max_depth {
+            true => candidates[random.below(candidates.len())],
+            false => self.shortest_rule(variable).unwrap(),
+        };
+        for symbol in &self.rules[rule].output.data {
+            match symbol {
+                MixedChar::Terminal(terminal) => output.push_terminal(*terminal),
+                MixedChar::Variable(next) => self.expand_random(random, *next, depth + 1, max_depth, output),
+            }
+        }
+    }
+
+    fn expand_shortest(&self, variable: Variable, used: &mut Vec<usize>, output: &mut TerminalString) {
+        let rule = self.shortest_rule(variable).unwrap();
+        self.expand_rule(rule, used, output);
+    }
+
+    fn expand_rule(&self, rule: usize, used: &mut Vec<usize>, output: &mut TerminalString) {
+        used.push(rule);
+        for symbol in &self.rules[rule].output.data {
+            match symbol {
+                MixedChar::Terminal(terminal) => output.push_terminal(*terminal),
+                MixedChar::Variable(next) => self.expand_shortest(*next, used, output),
+            }
+        }
+    }
+
+    /// Shortest chain of (rule, position) leading from the start variable down to `target`
+    fn path_to(&self, target: Variable) -> Option<Vec<(usize, usize)>> {
+        let mut parent: HashMap<Variable, Option<(Variable, usize, usize)>> = HashMap::from([(self.start, None)]);
+        let mut queue = VecDeque::from([self.start]);
+        while let Some(variable) = queue.pop_front() {
+            if variable == target {
+                break;
+            }
+            for rule in self.rules_of(variable) {
+                for (position, symbol) in self.rules[rule].output.data.iter().enumerate() {
+                    let MixedChar::Variable(next) = symbol else {
+                        continue;
+                    };
+                    if !parent.contains_key(next) {
+                        parent.insert(*next, Some((variable, rule, position)));
+                        queue.push_back(*next);
+                    }
+                }
+            }
+        }
+        let mut path = vec![];
+        let mut current = *parent.get(&target)?;
+        while let Some((variable, rule, position)) = current {
+            path.push((rule, position));
+            current = parent[&variable];
+        }
+        path.reverse();
+        Some(path)
+    }
+
+    fn expand_path(&self, path: &[(usize, usize)], target_rule: usize, used: &mut Vec<usize>, output: &mut TerminalString) {
+        let Some(((rule, forced), rest)) = path.split_first() else {
+            return self.expand_rule(target_rule, used, output);
+        };
+        used.push(*rule);
+        for (position, symbol) in self.rules[*rule].output.data.iter().enumerate() {
+            match symbol {
+                MixedChar::Terminal(terminal) => output.push_terminal(*terminal),
+                MixedChar::Variable(_) if position == *forced => self.expand_path(rest, target_rule, used, output),
+                MixedChar::Variable(next) => self.expand_shortest(*next, used, output),
+            }
+        }
+    }
+
+    /// Sentences which together use every production at least once,
+    /// along with the rules no sentence can use because they are unreachable or never end in terminals
+    pub fn coverage(&self) -> (Vec<TerminalString>, Vec<usize>) {
+        let mut covered = vec![false; self.rules.len()];
+        let mut sentences = vec![];
+        let mut uncovered = vec![];
+        for (rule, _) in self.rules.iter().enumerate() {
+            if covered[rule] {
+                continue;
+            }
+            let path = self.path_to(self.rules[rule].clause);
+            let Some(path) = path.filter(|_| self.rule_height[rule].is_some()) else {
+                uncovered.push(rule);
+                continue;
+            };
+            let mut used = vec![];
+            let mut output = TerminalString::new();
+            self.expand_path(&path, rule, &mut used, &mut output);
+            for id in used {
+                covered[id] = true;
+            }
+            sentences.push(output);
+        }
+        (sentences, uncovered)
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum Mutation {
+    Insert,
+    Delete,
+    Swap,
+}
+
+/// Small edit of a sentence, a terminal inserted, deleted or two neighbours swapped
+pub fn mutate(sentence: &TerminalString, alphabet: &[Terminal], random: &mut Random) -> TerminalString {
+    let mut data = sentence.data.clone();
+    let mut mutations = vec![];
+    if !alphabet.is_empty() {
+        mutations.push(Mutation::Insert);
+    }
+    if !data.is_empty() {
+        mutations.push(Mutation::Delete);
+    }
+    if data.len() > 1 {
+        mutations.push(Mutation::Swap);
+    }
+    // an empty sentence of a grammar without terminals cannot change
+    if mutations.is_empty() {
+        return sentence.clone();
+    }
+    match mutations[random.below(mutations.len())] {
+        Mutation::Insert => {
+            let position = random.below(data.len() + 1);
+            data.insert(position, alphabet[random.below(alphabet.len())]);
+        }
+        Mutation::Delete => {
+            data.remove(random.below(data.len()));
+        }
+        Mutation::Swap => {
+            let position = random.below(data.len() - 1);
+            data.swap(position, position + 1);
+        }
+    }
+    TerminalString { data }
+}
+
+/// Covering and random sentences of the grammar labeled accepted, then mutants of them
+/// labeled rejected when the earley recognizer rejects them, without duplicates
+pub fn corpus(rules: &[Rule], start: Variable, count: usize, random: &mut Random) -> Vec<Sample> {
+    let generator = SentenceGenerator::new(rules, start);
+    let reference = Earley::new(rules, start);
+    let alphabet = alphabet(rules);
+
+    let mut valid = generator.coverage().0;
+    valid.extend((0..count).filter_map(|_| generator.random(random, 8)));
+    let mut seen = HashSet::new();
+    let mut samples = vec![];
+    for sentence in &valid {
+        if seen.insert(sentence.clone()) {
+            samples.push(Sample { expected: Some(true), sentence: sentence.clone() });
+        }
+    }
+    for sentence in &valid {
+        for _ in 0..3 {
+            let mutant = mutate(sentence, &alphabet, random);
+            if seen.insert(mutant.clone()) && !reference.parse(&mutant).accepts() {
+                samples.push(Sample { expected: Some(false), sentence: mutant });
+            }
+        }
+    }
+    samples
+}
+
+/// Every terminal used by the rules
+pub fn alphabet(rules: &[Rule]) -> Vec<Terminal> {
+    let terminals: std::collections::BTreeSet<Terminal> = rules.iter()
+        .flat_map(|rule| rule.output.data.iter().filter_map(|symbol| Terminal::try_from(symbol).ok()))
+        .collect();
+    terminals.into_iter().collect()
+}
+
+/// Sentence with the verdict a correct parser has to give, `None` when the file did not say
+pub struct Sample {
+    pub expected: Option<bool>,
+    pub sentence: TerminalString,
+}
+
+/// One sentence per line, the format read back by the batch parser
+pub fn write_sentences(file_path: &str, sentences: &[TerminalString]) -> io::Result<()> {
+    let content: String = sentences.iter().map(|sentence| format!("{}\n", sentence)).collect();
+    fs::write(file_path, content)
+}
+
+/// One `accept` or `reject` label, a tab and the sentence per line
+pub fn write_corpus(file_path: &str, samples: &[Sample]) -> io::Result<()> {
+    let content: String = samples.iter().map(|sample| {
+        let label = match sample.expected {
+            Some(true) => "accept\t",
+            Some(false) => "reject\t",
+            None => "",
+        };
+        format!("{}{}\n", label, sample.sentence)
+    }).collect();
+    fs::write(file_path, content)
+}
+
+/// Reads both plain sentence files and labeled corpora
+pub fn read_corpus(file_path: &str) -> io::Result<Vec<Sample>> {
+    let content = fs::read_to_string(file_path)?;
+    Ok(content.lines().map(|line| {
+        let (expected, sentence) = match line.split_once('\t') {
+            Some(("accept", sentence)) => (Some(true), sentence),
+            Some(("reject", sentence)) => (Some(false), sentence),
+            _ => (None, line),
+        };
+        let sentence = TerminalString { data: sentence.chars().map(|symbol| Terminal { symbol }).collect() };
+        Sample { expected, sentence }
+    }).collect())
+}
+
+/// Statement list grammar with `statements` kinds of statement, each a keyword followed by
+/// a list of `words` different words, `statements * (2 * words + 1) + 2` rules
+pub fn benchmark_grammar(statements: usize, words: usize) -> Vec<Rule> {
+    let mut symbols = (0xE000..).filter_map(char::from_u32);
+    let mut rules = vec![];
+    let statement = symbols.next().unwrap();
+    let mut list = Rule::new('E');
+    list.add_variable('E');
+    list.add_terminal(';');
+    list.add_variable(statement);
+    rules.push(list);
+    let mut single = Rule::new('E');
+    single.add_variable(statement);
+    rules.push(single);
+    for _ in 0..statements {
+        let (keyword, words_variable) = (symbols.next().unwrap(), symbols.next().unwrap());
+        let mut rule = Rule::new(statement);
+        rule.add_terminal(keyword);
+        rule.add_variable(words_variable);
+        rules.push(rule);
+        for _ in 0..words {
+            let word = symbols.next().unwrap();
+            let mut more = Rule::new(words_variable);
+            more.add_terminal(word);
+            more.add_variable(words_variable);
+            rules.push(more);
+            let mut last = Rule::new(words_variable);
+            last.add_terminal(word);
+            rules.push(last);
+        }
+    }
+    rules
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{corpus, mutate, Random, SentenceGenerator};
+    use crate::earley::Earley;
+    use crate::parsing::tests::lr_one;
+    use crate::parsing::ParsingProcess;
+    use crate::parsing_table::lr_one::StateMachine;
+    use crate::syntax::tests::{rules, sentence};
+    use crate::syntax::{self, TerminalString, Variable};
+
+    const EXPRESSION: [&str; 6] = ["E:E+T", "E:T", "T:T*F", "T:F", "F:(E)", "F:i"];
+
+    fn accepts(machine: &StateMachine, sentence: &TerminalString) -> bool {
+        let input: Vec<char> = sentence.data.iter().map(|terminal| terminal.symbol).chain([syntax::END_TERMINAL]).collect();
+        ParsingProcess::new(&input).parse(machine).is_ok()
+    }
+
+    #[test]
+    fn random_sentences_are_accepted() {
+        let machine = lr_one(&EXPRESSION);
+        let rules = rules(&EXPRESSION);
+        let generator = SentenceGenerator::new(&rules, Variable { symbol: 'E' });
+        let mut random = Random::new(7);
+        for _ in 0..200 {
+            let sentence = generator.random(&mut random, 6).unwrap();
+            assert!(accepts(&machine, &sentence), "{}", sentence);
+        }
+    }
+
+    #[test]
+    fn same_seed_same_sentences() {
+        let rules = rules(&EXPRESSION);
+        let generator = SentenceGenerator::new(&rules, Variable { symbol: 'E' });
+        let (mut first, mut second) = (Random::new(42), Random::new(42));
+        for _ in 0..20 {
+            assert_eq!(generator.random(&mut first, 5), generator.random(&mut second, 5));
+        }
+    }
+
+    #[test]
+    fn uncovered_rules() {
+        // A is never reached from E, B never ends in terminals
+        let rules = rules(&["E:E+T", "E:T", "T:i", "A:i", "T:B", "B:bB"]);
+        let generator = SentenceGenerator::new(&rules, Variable { symbol: 'E' });
+        let (sentences, uncovered) = generator.coverage();
+        assert_eq!(uncovered, [3, 4, 5]);
+        let machine = lr_one(&["E:E+T", "E:T", "T:i"]);
+        assert!(sentences.iter().all(|sentence| accepts(&machine, sentence)));
+    }
+
+    #[test]
+    fn coverage_uses_every_reachable_rule() {
+        let rules = rules(&EXPRESSION);
+        let (sentences, uncovered) = SentenceGenerator::new(&rules, Variable { symbol: 'E' }).coverage();
+        assert!(uncovered.is_empty());
+        let text: Vec<String> = sentences.iter().map(ToString::to_string).collect();
+        // the operators and parentheses need their own rules, E:T, T:F and F:i are under every sentence
+        for symbol in ['+', '*', '(', 'i'] {
+            assert!(text.iter().any(|sentence| sentence.contains(symbol)), "{:?}", text);
+        }
+        let machine = lr_one(&EXPRESSION);
+        assert!(sentences.iter().all(|sentence| accepts(&machine, sentence)));
+    }
+
+    #[test]
+    fn reject_mutants_are_rejected_by_earley() {
+        let rules = rules(&EXPRESSION);
+        let reference = Earley::new(&rules, Variable { symbol: 'E' });
+        let samples = corpus(&rules, Variable { symbol: 'E' }, 50, &mut Random::new(3));
+        assert!(samples.iter().any(|sample| sample.expected == Some(false)));
+        for sample in samples {
+            assert_eq!(Some(reference.parse(&sample.sentence).accepts()), sample.expected, "{}", sample.sentence);
+        }
+    }
+
+    #[test]
+    fn mutate_without_terminals() {
+        let mut random = Random::new(1);
+        assert_eq!(mutate(&sentence(""), &[], &mut random), sentence(""));
+        for _ in 0..10 {
+            let mutant = mutate(&sentence("ab"), &[], &mut random).to_string();
+            assert!(["a", "b", "ba"].contains(&mutant.as_str()), "{}", mutant);
+        }
+    }
+}
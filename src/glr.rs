@@ -0,0 +1,169 @@
+use std::collections::HashMap;
+
+use crate::forest::{Family, Forest};
+use crate::parsing_table::{Action, IStateMachine};
+use crate::syntax::{Rule, TerminalString};
+
+/// Tomita's generalized LR parsing on a graph structured stack
+/// https://doi.org/10.3115/981210.981268
+pub struct Glr<'machine, Machine> {
+    machine: &'machine Machine,
+    rules: &'machine [Rule],
+}
+
+struct StackNode {
+    state: usize,
+    position: usize,
+    // (previous stack node, forest node of the symbol between them)
+    edges: Vec<(usize, usize)>,
+}
+
+#[derive(Default)]
+struct GraphStack {
+    nodes: Vec<StackNode>,
+    levels: Vec<HashMap<usize, usize>>,
+}
+
+impl GraphStack {
+    /// Stack node with `state` at `position`, the flag is set when the node was just created
+    fn node(&mut self, state: usize, position: usize) -> (usize, bool) {
+        while self.levels.len() <= position {
+            self.levels.push(HashMap::new());
+        }
+        if let Some(node) = self.levels[position].get(&state) {
+            return (*node, false);
+        }
+        let node = self.nodes.len();
+        self.nodes.push(StackNode { state, position, edges: vec![] });
+        self.levels[position].insert(state, node);
+        (node, true)
+    }
+
+    fn add_edge(&mut self, from: usize, to: usize, label: usize) -> bool {
+        if self.nodes[from].edges.contains(&(to, label)) {
+            return false;
+        }
+        self.nodes[from].edges.push((to, label));
+        true
+    }
+
+    /// Every node `length` edges below `node` with the labels passed on the way, leftmost first
+    fn paths(&self, node: usize, length: usize) -> Vec<(usize, Vec<usize>)> {
+        if length == 0 {
+            return vec![(node, vec![])];
+        }
+        let mut output = vec![];
+        for (previous, label) in &self.nodes[node].edges {
+            for (end, mut labels) in self.paths(*previous, length - 1) {
+                labels.push(*label);
+                output.push((end, labels));
+            }
+        }
+        output
+    }
+}
+
+impl<'machine, Machine> Glr<'machine, Machine>
+where Machine: IStateMachine<'machine> {
+    pub fn new(machine: &'machine Machine, rules: &'machine [Rule]) -> Self {
+        Self { machine, rules }
+    }
+
+    /// Every parse of the input packed into one forest, `None` when no stack accepts
+    pub fn parse(&self, input: &TerminalString) -> Option<Forest> {
+        let mut forest = Forest::new(self.rules);
+        let mut stack = GraphStack::default();
+        let mut accepted = false;
+        let (bottom, _) = stack.node(0, 0);
+        let mut frontier = vec![bottom];
+
+        for position in 0..=input.data.len() {
+            let lookahead = input.get(position);
+            let mut shifts = vec![];
+            // reductions are repeated until no new edge shows up, an edge added below an already
+            // processed node (through ε-reductions) can open paths that node has not seen yet
+            loop {
+                let mut changed = false;
+                let mut index = 0;
+                while let Some(node) = frontier.get(index).copied() {
+                    index += 1;
+                    for action in self.machine.actions(stack.nodes[node].state, lookahead) {
+                        match action {
+                            Action::Accept => accepted = true,
+                            Action::Reject => {}
+                            Action::Shift(next) => shifts.push((node, next)),
+                            Action::Reduce(rule) => {
+                                let variable = self.rules[rule].clause;
+                                for (end, children) in stack.paths(node, self.rules[rule].len()) {
+                                    let start = stack.nodes[end].position;
+                                    let (label, _) = forest.node(variable.into(), start, position);
+                                    forest.add_family(label, Family { rule, children });
+                                    let goto = self.machine.reduce_state(stack.nodes[end].state, variable);
+                                    let (target, created) = stack.node(goto, position);
+                                    if created {
+                                        frontier.push(target);
+                                    }
+                                    changed |= stack.add_edge(target, end, label);
+                                }
+                            }
+                        }
+                    }
+                }
+                if !changed {
+                    break;
+                }
+            }
+            let Some(terminal) = lookahead else {
+                break;
+            };
+            let (label, _) = forest.node(terminal.into(), position, position + 1);
+            frontier = vec![];
+            for (node, next) in shifts {
+                let (target, created) = stack.node(next, position + 1);
+                stack.add_edge(target, node, label);
+                if created {
+                    frontier.push(target);
+                }
+            }
+            if frontier.is_empty() {
+                break;
+            }
+        }
+        if !accepted {
+            return None;
+        }
+        let start = self.rules[0].output.data.first().copied()?;
+        forest.root = forest.find(start, 0, input.without_end().len());
+        Some(forest)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Glr;
+    use crate::parsing::tests::lr_one;
+    use crate::parsing_table::IStateMachine;
+    use crate::syntax::tests::sentence;
+    use crate::syntax;
+
+    fn trees(grammar: &[&str], input: &str) -> Option<Vec<String>> {
+        let machine = lr_one(grammar);
+        let input = sentence(&format!("{}{}", input, syntax::END_TERMINAL));
+        let forest = Glr::new(&machine, machine.rules()).parse(&input)?;
+        Some(forest.trees(10).iter().map(ToString::to_string).collect())
+    }
+
+    #[test]
+    fn both_derivations_of_an_ambiguous_sentence() {
+        let trees = trees(&["E:E+E", "E:i"], "i+i+i").unwrap();
+        assert_eq!(trees.len(), 2);
+        assert!(trees.contains(&"E(E(i) + E(E(i) + E(i)))".to_string()));
+        assert!(trees.contains(&"E(E(E(i) + E(i)) + E(i))".to_string()));
+        assert_eq!(self::trees(&["E:E+E", "E:i"], "i+"), None);
+    }
+
+    #[test]
+    fn single_tree_without_conflicts() {
+        assert_eq!(trees(&["E:E+T", "E:T", "T:i"], "i+i+i").unwrap(), ["E(E(E(T(i)) + T(i)) + T(i))"]);
+    }
+}
@@ -0,0 +1,19 @@
+use std::fmt::Display;
+use crate::itemset::Item as _;
+use super::super::lr_k::ItemSets;
+
+impl Display for ItemSets {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (number, item_set) in self.sets.iter().enumerate() {
+            writeln!(f, "Item set {}", number)?;
+            for item in &item_set.items {
+                item.display(&self.rules).fmt(f)?;
+                if item.kernel() {
+                    write!(f, "*")?;
+                }
+                writeln!(f)?;
+            }
+        }
+        Ok(())
+    }
+}
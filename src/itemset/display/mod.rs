@@ -2,8 +2,10 @@
 
 
 pub mod item_lookahead;
+pub mod item_lookahead_k;
 pub mod item_no_lookahead;
 mod lr_one;
+mod lr_k;
 mod lr_zero;
 
 pub const DOT: char = '•';
@@ -0,0 +1,288 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use crate::data_structure::bit_set::BitSet;
+use crate::itemset::item_lookahead::ItemSet;
+use crate::itemset::lr_one::ItemSets;
+use crate::syntax::{MixedChar, Rule, Terminal};
+
+/// Kernel of a state, (item id, lookaheads) sorted by item id
+pub(super) type Kernel = Vec<(usize, BitSet)>;
+
+/// Items of a closure started from one item, (rule, spontaneous lookaheads, whether the lookaheads of the item carry over)
+pub(super) type Closure = Vec<(usize, BitSet, bool)>;
+
+/// The grammar with dense ids for symbols, terminals and items (rule, dot)
+pub(super) struct Grammar<'a> {
+    rules: &'a [Rule],
+    terminals: Vec<Terminal>,
+    rules_of: HashMap<MixedChar, Vec<usize>>,
+    /// id of item (rule, 0), item (rule, dot) is `offset[rule] + dot`
+    offset: Vec<usize>,
+    item_rule: Vec<usize>,
+    /// symbol after the dot
+    item_symbol: Vec<Option<usize>>,
+    symbols: Vec<MixedChar>,
+    /// FIRST of what follows the symbol after the dot and whether it is nullable
+    item_rest: Vec<(BitSet, bool)>,
+}
+
+impl<'a> Grammar<'a> {
+    pub(super) fn new(rules: &'a [Rule]) -> Self {
+        let symbols: BTreeSet<MixedChar> = rules.iter()
+            .flat_map(|rule| rule.output.data.iter().copied().chain([MixedChar::Variable(rule.clause)]))
+            .collect();
+        let symbols: Vec<MixedChar> = symbols.into_iter().collect();
+        let symbol_id: HashMap<MixedChar, usize> = symbols.iter().enumerate().map(|(id, symbol)| (*symbol, id)).collect();
+        let mut terminals: Vec<Terminal> = symbols.iter().filter_map(|symbol| Terminal::try_from(symbol).ok()).collect();
+        terminals.push(Terminal::end());
+        let terminal_id: HashMap<Terminal, usize> = terminals.iter().enumerate().map(|(id, terminal)| (*terminal, id)).collect();
+
+        let mut rules_of: HashMap<MixedChar, Vec<usize>> = HashMap::new();
+        for (id, rule) in rules.iter().enumerate() {
+            rules_of.entry(MixedChar::Variable(rule.clause)).or_default().push(id);
+        }
+
+        // FIRST and nullable of every variable, grown until a fixed point
+        let mut first: HashMap<MixedChar, BitSet> = HashMap::new();
+        let mut nullable: BTreeSet<MixedChar> = BTreeSet::new();
+        loop {
+            let mut changed = false;
+            for rule in rules {
+                let clause = MixedChar::Variable(rule.clause);
+                let mut firsts = BitSet::new(terminals.len());
+                let mut all_nullable = true;
+                for symbol in &rule.output.data {
+                    match symbol {
+                        MixedChar::Terminal(terminal) => {
+                            firsts.insert(terminal_id[terminal]);
+                        }
+                        MixedChar::Variable(_) => {
+                            if let Some(other) = first.get(symbol) {
+                                firsts.union_with(other);
+                            }
+                        }
+                    }
+                    if !nullable.contains(symbol) {
+                        all_nullable = false;
+                        break;
+                    }
+                }
+                changed |= first.entry(clause).or_insert_with(|| BitSet::new(terminals.len())).union_with(&firsts);
+                changed |= all_nullable && nullable.insert(clause);
+            }
+            if !changed {
+                break;
+            }
+        }
+
+        let mut offset = vec![];
+        let mut item_rule = vec![];
+        let mut item_symbol = vec![];
+        let mut item_rest = vec![];
+        for (id, rule) in rules.iter().enumerate() {
+            offset.push(item_rule.len());
+            let mut rest = vec![(BitSet::new(terminals.len()), true); rule.len() + 1];
+            for dot in (0..rule.len()).rev() {
+                let symbol = &rule.output.data[dot];
+                let (mut firsts, rest_nullable) = rest[dot + 1].clone();
+                let mut symbol_first = BitSet::new(terminals.len());
+                match symbol {
+                    MixedChar::Terminal(terminal) => {
+                        symbol_first.insert(terminal_id[terminal]);
+                    }
+                    MixedChar::Variable(_) => {
+                        symbol_first = first.get(symbol).cloned().unwrap_or_else(|| BitSet::new(terminals.len()));
+                    }
+                }
+                // rest[dot] is FIRST(output[dot..]), built from the one after it
+                match nullable.contains(symbol) {
+                    true => {
+                        firsts.union_with(&symbol_first);
+                        rest[dot] = (firsts, rest_nullable);
+                    }
+                    false => rest[dot] = (symbol_first, false),
+                }
+            }
+            for dot in 0..=rule.len() {
+                item_rule.push(id);
+                item_symbol.push(rule.output.data.get(dot).map(|symbol| symbol_id[symbol]));
+                item_rest.push(rest.get(dot + 1).cloned().unwrap_or((BitSet::new(terminals.len()), true)));
+            }
+        }
+        Self { rules, terminals, rules_of, offset, item_rule, item_symbol, symbols, item_rest }
+    }
+
+    /// Closure of a single item with a placeholder lookahead, computed once per item
+    pub(super) fn closure_of(&self, item: usize) -> Closure {
+        let Some(symbol) = self.item_symbol[item] else {
+            return vec![];
+        };
+        let mut found: BTreeMap<usize, (BitSet, bool)> = BTreeMap::new();
+        let mut pending = vec![];
+        let (rest, rest_nullable) = &self.item_rest[item];
+        for rule in self.rules_of.get(&self.symbols[symbol]).into_iter().flatten() {
+            found.insert(*rule, (rest.clone(), *rest_nullable));
+            pending.push(*rule);
+        }
+        while let Some(rule) = pending.pop() {
+            let start = self.offset[rule];
+            let Some(symbol) = self.item_symbol[start] else {
+                continue;
+            };
+            let (spontaneous, propagate) = found[&rule].clone();
+            let (rest, rest_nullable) = &self.item_rest[start];
+            let mut lookahead = rest.clone();
+            if *rest_nullable {
+                lookahead.union_with(&spontaneous);
+            }
+            for next in self.rules_of.get(&self.symbols[symbol]).into_iter().flatten() {
+                let entry = found.entry(*next).or_insert_with(|| (BitSet::new(self.terminals.len()), false));
+                let mut changed = entry.0.union_with(&lookahead);
+                if *rest_nullable && propagate && !entry.1 {
+                    entry.1 = true;
+                    changed = true;
+                }
+                if changed {
+                    pending.push(*next);
+                }
+            }
+        }
+        found.into_iter().map(|(rule, (spontaneous, propagate))| (rule, spontaneous, propagate)).collect()
+    }
+
+    /// Number of items (rule, dot)
+    pub(super) fn item_count(&self) -> usize {
+        self.item_rule.len()
+    }
+
+    /// Kernel of the first state, the accept item with the end of input
+    pub(super) fn start(&self) -> Kernel {
+        let mut end = BitSet::new(self.terminals.len());
+        end.insert(self.terminals.len() - 1);
+        vec![(self.offset[0], end)]
+    }
+
+    /// Items of the state with the kernel and the kernels of its successors in symbol order,
+    /// the closures of the kernel items must be computed
+    pub(super) fn expand(&self, kernel: &Kernel, closures: &[Option<Closure>]) -> (ItemSet, Vec<(MixedChar, Kernel)>) {
+        let mut non_kernel: BTreeMap<usize, BitSet> = BTreeMap::new();
+        for (item, lookahead) in kernel {
+            for (rule, spontaneous, propagate) in closures[*item].as_ref().unwrap() {
+                let entry = non_kernel.entry(*rule).or_insert_with(|| BitSet::new(self.terminals.len()));
+                entry.union_with(spontaneous);
+                if *propagate {
+                    entry.union_with(lookahead);
+                }
+            }
+        }
+
+        let mut set = ItemSet::new();
+        let mut gotos: BTreeMap<usize, Kernel> = BTreeMap::new();
+        let kernel_items = kernel.iter().map(|(item, lookahead)| (*item, lookahead, true));
+        let closure_items = non_kernel.iter().map(|(rule, lookahead)| (self.offset[*rule], lookahead, false));
+        for (item, lookahead, kernel) in kernel_items.chain(closure_items) {
+            let rule_number = self.item_rule[item];
+            let dot = item - self.offset[rule_number];
+            let terminals: Vec<Terminal> = lookahead.iter().map(|terminal| self.terminals[terminal]).collect();
+            match kernel {
+                true => set.add_kernel(&self.rules[rule_number], dot, rule_number, &terminals),
+                false => set.add_rule(&self.rules[rule_number], dot, rule_number, &terminals),
+            }
+            if let Some(symbol) = self.item_symbol[item] {
+                gotos.entry(symbol).or_default().push((item + 1, lookahead.clone()));
+            }
+        }
+        let successors = gotos.into_iter()
+            .map(|(symbol, mut kernel)| {
+                kernel.sort_by_key(|(item, _)| *item);
+                (self.symbols[symbol], kernel)
+            })
+            .collect();
+        (set, successors)
+    }
+}
+
+/// Interned kernels, numbered in the order they are first reached
+pub(super) struct Kernels {
+    pub(super) kernels: Vec<Kernel>,
+    index: HashMap<Kernel, usize>,
+}
+
+impl Kernels {
+    pub(super) fn new(start: Kernel) -> Self {
+        Self { index: HashMap::from([(start.clone(), 0)]), kernels: vec![start] }
+    }
+
+    /// Number of every successor, a kernel not seen before gets the next one
+    pub(super) fn number(&mut self, successors: Vec<(MixedChar, Kernel)>) -> Vec<(MixedChar, usize)> {
+        successors.into_iter()
+            .map(|(symbol, kernel)| {
+                let next = *self.index.entry(kernel.clone()).or_insert_with(|| {
+                    self.kernels.push(kernel);
+                    self.kernels.len() - 1
+                });
+                (symbol, next)
+            })
+            .collect()
+    }
+}
+
+impl ItemSets {
+    /// Same item sets and numbering as `generate_next`, states are keyed by their interned kernel,
+    /// lookaheads are bitsets and the closure of every item is computed once
+    pub fn generate_fast(&mut self) {
+        let grammar = Grammar::new(&self.rules);
+        let mut closures: Vec<Option<Closure>> = vec![None; grammar.item_count()];
+        let mut kernels = Kernels::new(grammar.start());
+        let mut sets = vec![];
+        let mut ordering_map = vec![];
+
+        let mut current = 0;
+        while current < kernels.kernels.len() {
+            for (item, _) in &kernels.kernels[current] {
+                if closures[*item].is_none() {
+                    closures[*item] = Some(grammar.closure_of(*item));
+                }
+            }
+            let (set, successors) = grammar.expand(&kernels.kernels[current], &closures);
+            ordering_map.push(kernels.number(successors));
+            sets.push(set);
+            current += 1;
+        }
+        self.sets = sets;
+        self.ordering_map = ordering_map;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::generate;
+    use crate::itemset::lr_one::ItemSets;
+    use crate::syntax::Rule;
+
+    fn itemsets(rules: &[Rule], generate: fn(&mut ItemSets)) -> ItemSets {
+        let mut itemsets = ItemSets::new('E');
+        for rule in rules {
+            itemsets.add_rule(rule.clone());
+        }
+        generate(&mut itemsets);
+        itemsets
+    }
+
+    fn assert_same_as_generate_next(rules: &[Rule]) {
+        let (slow, fast) = (itemsets(rules, ItemSets::generate_next), itemsets(rules, ItemSets::generate_fast));
+        assert_eq!(slow.sets, fast.sets);
+        assert_eq!(slow.ordering_map, fast.ordering_map);
+    }
+
+    #[test]
+    fn benchmark_grammar() {
+        assert_same_as_generate_next(&generate::benchmark_grammar(6, 4));
+    }
+
+    #[test]
+    fn grammar_with_empty_rules() {
+        let rules = ["E:TA", "A:+TA", "A:", "T:FB", "B:*FB", "B:", "F:(E)", "F:i"];
+        assert_same_as_generate_next(&rules.map(|rule| Rule::try_from(rule).unwrap()));
+    }
+}
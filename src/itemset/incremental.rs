@@ -0,0 +1,252 @@
+use std::collections::{BTreeSet, HashMap, VecDeque};
+
+use crate::first_follow::First;
+use crate::itemset::Item as _;
+use crate::itemset::item_lookahead::{Item, ItemSet};
+use crate::itemset::lr_one::ItemSets;
+use crate::syntax::{self, MixedChar, Rule, Variable};
+
+/// `changed` and every variable with a rule mentioning one of them, the variables whose
+/// first set or expansion may depend on the rules of `changed`
+fn touched(rules: &[&Rule], changed: Variable) -> BTreeSet<Variable> {
+    let mut touched = BTreeSet::from([changed]);
+    loop {
+        let before = touched.len();
+        for rule in rules {
+            if rule.output.data.iter().any(|symbol| matches!(symbol, MixedChar::Variable(variable) if touched.contains(variable))) {
+                touched.insert(rule.clause);
+            }
+        }
+        if touched.len() == before {
+            return touched;
+        }
+    }
+}
+
+impl ItemSets {
+    /// Adds the rule, an already generated automaton is updated in place.
+    /// Returns the states whose items were recomputed or whose number changed
+    pub fn add_rule(&mut self, rule: Rule) -> Vec<usize> {
+        let changed = rule.clause;
+        let old_rules = self.rules.clone();
+        self.rules.push(rule);
+        match self.sets.is_empty() {
+            true => vec![],
+            false => self.regenerate(&old_rules, changed, Some),
+        }
+    }
+
+    /// Removes the rule, rules after it move one number down.
+    /// Returns the states whose items were recomputed or whose number changed,
+    /// `None` when there is no such rule or it is the accept rule
+    pub fn remove_rule(&mut self, rule_number: usize) -> Option<Vec<usize>> {
+        if rule_number == 0 || rule_number >= self.rules.len() {
+            return None;
+        }
+        let old_rules = self.rules.clone();
+        let removed = self.rules.remove(rule_number);
+        if self.sets.is_empty() {
+            return Some(vec![]);
+        }
+        Some(self.regenerate(&old_rules, removed.clause, |old| match old.cmp(&rule_number) {
+            std::cmp::Ordering::Less => Some(old),
+            std::cmp::Ordering::Equal => None,
+            std::cmp::Ordering::Greater => Some(old - 1),
+        }))
+    }
+
+    /// Walks the automaton again from the start state, a state whose items never reach a variable touched
+    /// by the edit keeps its old items and number. New states take the numbers of the states that are gone
+    fn regenerate(&mut self, old_rules: &[Rule], changed: Variable, renumber: impl Fn(usize) -> Option<usize>) -> Vec<usize> {
+        let touched = touched(&old_rules.iter().chain(&self.rules).collect::<Vec<_>>(), changed);
+        let involves_touched = |item: &Item| old_rules[item.rule_number].output.data[item.dot()..].iter()
+            .any(|symbol| matches!(symbol, MixedChar::Variable(variable) if touched.contains(variable)));
+
+        // old states with rule numbers of the new rules, keyed by their kernel. A state closing over a
+        // removed rule keeps its number but not its items, one whose kernel holds a removed rule is gone
+        let mut old_states: HashMap<ItemSet, (usize, Option<ItemSet>)> = HashMap::new();
+        for (number, set) in self.sets.iter().enumerate() {
+            let mut kernel = ItemSet::new();
+            let mut full = ItemSet::new();
+            let mut reusable = !set.items.iter().any(involves_touched);
+            for item in &set.items {
+                let Some(rule_number) = renumber(item.rule_number) else {
+                    reusable = false;
+                    continue;
+                };
+                let item = Item::new(rule_number, item.dot(), item.follow(), item.kernel());
+                if item.kernel() {
+                    kernel.add_item(&self.rules, item.clone());
+                }
+                full.add_item(&self.rules, item);
+            }
+            if set.items.iter().any(|item| item.kernel() && renumber(item.rule_number).is_none()) {
+                continue;
+            }
+            old_states.insert(kernel, (number, reusable.then_some(full)));
+        }
+
+        let first = First::from_rule(&self.rules);
+        let mut start = ItemSet::new();
+        start.add_kernel(&self.rules[0], 0, 0, &[syntax::Terminal::end()]);
+        let mut index: HashMap<ItemSet, usize> = HashMap::from([(start.clone(), 0)]);
+        let mut kernels = vec![start];
+        let mut sets: Vec<ItemSet> = vec![];
+        let mut ordering_map: Vec<Vec<(MixedChar, usize)>> = vec![];
+        let mut recomputed = vec![];
+        let mut queue = VecDeque::from([0]);
+        while let Some(current) = queue.pop_front() {
+            let kernel = kernels[current].clone();
+            let set = match old_states.get(&kernel) {
+                Some((_, Some(full))) => full.clone(),
+                _ => {
+                    recomputed.push(current);
+                    kernel.get_non_kernel(&self.rules, &first)
+                }
+            };
+            let mut next_val = vec![];
+            for symbol in set.symbols.iter() {
+                let Some(next) = set.transitions(*symbol, &self.rules) else {
+                    continue;
+                };
+                let next_index = *index.entry(next.clone()).or_insert_with(|| {
+                    kernels.push(next);
+                    queue.push_back(kernels.len() - 1);
+                    kernels.len() - 1
+                });
+                next_val.push((*symbol, next_index));
+            }
+            sets.push(set);
+            ordering_map.push(next_val);
+        }
+
+        // surviving states keep their old number while it is still in range, the numbers left free go to
+        // the new states in the order they were found and then to the surviving states that did not fit
+        let old_number = |state: usize| old_states.get(&kernels[state]).map(|(old, _)| *old);
+        let mut number = vec![None; kernels.len()];
+        let mut taken = vec![false; kernels.len()];
+        for (state, number) in number.iter_mut().enumerate() {
+            if let Some(old) = old_number(state).filter(|old| *old < kernels.len()) {
+                *number = Some(old);
+                taken[old] = true;
+            }
+        }
+        let mut rest: Vec<usize> = (0..kernels.len()).filter(|state| number[*state].is_none()).collect();
+        rest.sort_by_key(|state| (old_number(*state).is_some(), old_number(*state), *state));
+        let free = (0..kernels.len()).filter(|number| !taken[*number]);
+        for (state, free) in rest.into_iter().zip(free) {
+            number[state] = Some(free);
+            if old_number(state).is_some() {
+                recomputed.push(state);
+            }
+        }
+        let number: Vec<usize> = number.into_iter().map(Option::unwrap).collect();
+        let mut order = vec![0; kernels.len()];
+        for (state, number) in number.iter().enumerate() {
+            order[*number] = state;
+        }
+        self.sets = order.iter().map(|state| sets[*state].clone()).collect();
+        self.ordering_map = order.iter()
+            .map(|state| ordering_map[*state].iter().map(|(symbol, next)| (*symbol, number[*next])).collect())
+            .collect();
+        let mut recomputed: Vec<usize> = recomputed.into_iter().map(|state| number[state]).collect();
+        recomputed.sort();
+        recomputed.dedup();
+        recomputed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeSet, HashMap};
+
+    use crate::itemset::Item as _;
+    use crate::itemset::lr_one::ItemSets;
+    use crate::syntax::{MixedChar, Rule, Terminal};
+
+    /// Items of every state written with their rule instead of its number, which changes on removal
+    fn described(itemsets: &ItemSets) -> Vec<BTreeSet<(String, usize, Terminal)>> {
+        itemsets.sets.iter()
+            .map(|set| set.items.iter().map(|item| (itemsets.rules[item.rule_number].to_string(), item.dot(), item.follow())).collect())
+            .collect()
+    }
+
+    fn generated(rules: &[Rule]) -> ItemSets {
+        let mut itemsets = ItemSets::new('E');
+        for rule in &rules[1..] {
+            itemsets.add_rule(rule.clone());
+        }
+        itemsets.generate_next();
+        itemsets
+    }
+
+    /// Same states and transitions as `generate_next` on the same rules, up to the numbering
+    fn assert_same_as_generate_next(itemsets: &ItemSets) {
+        let fresh = generated(&itemsets.rules);
+        let (states, fresh_states) = (described(itemsets), described(&fresh));
+        assert_eq!(states.len(), fresh_states.len());
+        let number: HashMap<_, usize> = fresh_states.iter().enumerate().map(|(state, items)| (items, state)).collect();
+        let renumbered: Vec<usize> = states.iter().map(|items| number[items]).collect();
+        assert_eq!(renumbered[0], 0);
+        for (state, next) in itemsets.ordering_map.iter().enumerate() {
+            let mut next: Vec<(MixedChar, usize)> = next.iter().map(|(symbol, target)| (*symbol, renumbered[*target])).collect();
+            let mut expected = fresh.ordering_map[renumbered[state]].clone();
+            next.sort();
+            expected.sort();
+            assert_eq!(next, expected);
+        }
+    }
+
+    fn add(itemsets: &mut ItemSets, rule: &str) -> Vec<usize> {
+        itemsets.add_rule(Rule::try_from(rule).unwrap())
+    }
+
+    /// Runs the edit, a state it did not list keeps its number and items
+    fn assert_edit(itemsets: &mut ItemSets, edit: impl FnOnce(&mut ItemSets) -> Vec<usize>) -> Vec<usize> {
+        let before = described(itemsets);
+        let recomputed = edit(itemsets);
+        assert!(recomputed.windows(2).all(|pair| pair[0] < pair[1]));
+        for (state, items) in described(itemsets).iter().enumerate() {
+            if !recomputed.contains(&state) {
+                assert_eq!(before.get(state), Some(items), "state {} changed without being listed", state);
+            }
+        }
+        assert_same_as_generate_next(itemsets);
+        recomputed
+    }
+
+    #[test]
+    fn adds_and_removes() {
+        let mut itemsets = generated(&ItemSets::new('E').rules);
+        for rule in ["E:E+T", "E:T", "T:i"] {
+            assert_edit(&mut itemsets, |itemsets| add(itemsets, rule));
+        }
+        let count = itemsets.sets.len();
+        assert!(!assert_edit(&mut itemsets, |itemsets| add(itemsets, "T:(E)")).is_empty());
+        assert!(itemsets.sets.len() > count);
+
+        // F is not reachable yet, nothing changes
+        let states = described(&itemsets);
+        assert_eq!(assert_edit(&mut itemsets, |itemsets| add(itemsets, "F:x")), []);
+        assert_eq!(described(&itemsets), states);
+
+        assert!(!assert_edit(&mut itemsets, |itemsets| add(itemsets, "T:F")).is_empty());
+        // the rules are now ACCEPT:E, E:E+T, E:T, T:i, T:(E), F:x, T:F
+        assert_edit(&mut itemsets, |itemsets| itemsets.remove_rule(4).unwrap());
+        assert_edit(&mut itemsets, |itemsets| itemsets.remove_rule(1).unwrap());
+        assert_eq!(itemsets.remove_rule(0), None);
+        assert_eq!(itemsets.remove_rule(itemsets.rules.len()), None);
+    }
+
+    #[test]
+    fn removed_states_free_their_numbers() {
+        let mut itemsets = generated(&ItemSets::new('E').rules);
+        for rule in ["E:E+T", "E:T", "T:i", "T:(E)", "T:F", "F:x"] {
+            assert_edit(&mut itemsets, |itemsets| add(itemsets, rule));
+        }
+        // every state under an open parenthesis is gone, the states after them keep their numbers
+        let recomputed = assert_edit(&mut itemsets, |itemsets| itemsets.remove_rule(4).unwrap());
+        assert!(recomputed.contains(&0));
+        assert_edit(&mut itemsets, |itemsets| itemsets.remove_rule(1).unwrap());
+    }
+}
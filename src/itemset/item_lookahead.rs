@@ -1,8 +1,7 @@
 use std::collections::{BTreeSet, HashMap};
 use std::iter;
-use crate::first_follow::Follow;
+use crate::first_follow::First;
 use crate::itemset::Item as _;
-use crate::rule_depend::RuleGraph;
 use crate::syntax::{MixedChar, Rule, Terminal};
 
 #[derive(Hash, PartialEq, Eq, Clone, Debug, PartialOrd, Ord)]
@@ -105,13 +104,12 @@ impl ItemSet {
         }
     }
 
-    fn add_item(&mut self, rules: &[Rule], item: Item) {
+    pub(super) fn add_item(&mut self, rules: &[Rule], item: Item) -> bool {
         if let Some(character) = item.symbol(rules){
             self.symbols.insert(character);
         }
 
-        self.items.insert(item);
-
+        self.items.insert(item)
     }
 
     pub(crate) fn transitions(&self, transchar: MixedChar, rules: &[Rule]) -> Option<ItemSet> {
@@ -170,24 +168,95 @@ impl ItemSet {
         }
     }
 
-    pub(super) fn add_non_kernel(&mut self, rule_graph: &RuleGraph, rules: &[Rule], follows: &Follow, prev: Option<&ItemSet>) {
-        let non_kernels: Vec<usize> = rule_graph.gets_rule(self.symbols.iter().filter_map(|symbol| symbol.try_into().ok()));
-        for non_kernel in non_kernels.iter() {
-            let kernels = self.items.iter().map(|item| &item.rule_number);
-            let output = rules[*non_kernel].clause;
-            let Some(prev_set) = prev else {
-                let follow_set = follows.get_filtered(&output, kernels.chain(&non_kernels), iter::once((0, Terminal::end())));
-                self.add_rule(&rules[*non_kernel], 0, *non_kernel, &follow_set);
-                continue
+    /// Adds [B -> • γ, b] for every [A -> α • B β, a] and every b in FIRST(β a)
+    pub(super) fn add_non_kernel(&mut self, rules: &[Rule], first: &First) {
+        let mut pending: Vec<Item> = self.items.iter().cloned().collect();
+        while let Some(item) = pending.pop() {
+            let Some(MixedChar::Variable(variable)) = item.symbol(rules) else {
+                continue;
             };
-            let follow_set = follows.get_filtered(&output, kernels.chain(&non_kernels), prev_set.kernel_follow(rules));
-            self.add_rule(&rules[*non_kernel], 0, *non_kernel, &follow_set)
+            let follows = first.of(&rules[item.rule_number].output.data[item.dot + 1..], item.follow);
+            for (rule_number, _) in rules.iter().enumerate().filter(|(_, rule)| rule.clause == variable) {
+                for follow in &follows {
+                    let next = Item::new(rule_number, 0, *follow, false);
+                    if self.add_item(rules, next.clone()) {
+                        pending.push(next);
+                    }
+                }
+            }
         }
     }
 
-    pub(super) fn get_non_kernel(&self, rule_graph: &RuleGraph, rules: &[Rule], follows: &Follow, prev: Option<&ItemSet>) -> Self{
+    pub(super) fn get_non_kernel(&self, rules: &[Rule], first: &First) -> Self{
         let mut next = self.clone();
-        next.add_non_kernel(rule_graph, rules, follows, prev);
+        next.add_non_kernel(rules, first);
         next
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use crate::itemset::{Item as _, LROneItemSets, LRkItemSets};
+    use crate::syntax;
+
+    /// Items (rule, dot, lookahead) of every state of the dragon book grammar S -> CC, C -> cC | d,
+    /// with `$` for the end of input
+    const DRAGON_BOOK: [&[(usize, usize, char)]; 10] = [
+        &[(0, 0, '$'), (1, 0, '$'), (2, 0, 'c'), (2, 0, 'd'), (3, 0, 'c'), (3, 0, 'd')],
+        &[(0, 1, '$')],
+        &[(1, 1, '$'), (2, 0, '$'), (3, 0, '$')],
+        &[(2, 1, 'c'), (2, 1, 'd'), (2, 0, 'c'), (2, 0, 'd'), (3, 0, 'c'), (3, 0, 'd')],
+        &[(3, 1, 'c'), (3, 1, 'd')],
+        &[(1, 2, '$')],
+        &[(2, 1, '$'), (2, 0, '$'), (3, 0, '$')],
+        &[(3, 1, '$')],
+        &[(2, 2, 'c'), (2, 2, 'd')],
+        &[(2, 2, '$')],
+    ];
+
+    type Automaton = BTreeSet<BTreeSet<(usize, usize, char)>>;
+
+    fn lr_one(start: char, rules: &[&str]) -> Automaton {
+        let mut itemsets = LROneItemSets::new(start);
+        for rule in rules {
+            assert!(itemsets.add_from_string(rule));
+        }
+        itemsets.generate_next();
+        itemsets.sets.iter()
+            .map(|set| set.items.iter().map(|item| (item.rule_number, item.dot(), item.follow().symbol)).collect())
+            .collect()
+    }
+
+    fn lr_k(start: char, rules: &[&str]) -> Automaton {
+        let mut itemsets = LRkItemSets::new(start, 1);
+        for rule in rules {
+            assert!(itemsets.add_from_string(rule));
+        }
+        itemsets.generate_next();
+        itemsets.sets.iter()
+            .map(|set| set.items.iter().map(|item| (item.rule_number, item.dot(), item.follow()[0].symbol)).collect())
+            .collect()
+    }
+
+    #[test]
+    fn dragon_book_automaton() {
+        let expected: Automaton = DRAGON_BOOK.iter()
+            .map(|set| set.iter().map(|(rule, dot, follow)| (*rule, *dot, if *follow == '$' { syntax::END_TERMINAL } else { *follow })).collect())
+            .collect();
+        assert_eq!(lr_one('S', &["S:CC", "C:cC", "C:d"]), expected);
+    }
+
+    #[test]
+    fn same_as_lr_k_with_one_terminal() {
+        for (start, rules) in [
+            ('S', &["S:CC", "C:cC", "C:d"][..]),
+            ('S', &["S:L=R", "S:R", "L:*R", "L:i", "R:L"]),
+            ('E', &["E:TA", "A:+TA", "A:", "T:FB", "B:*FB", "B:", "F:(E)", "F:i"]),
+            ('E', &["E:E+E", "E:E*E", "E:(E)", "E:i"]),
+        ] {
+            assert_eq!(lr_one(start, rules), lr_k(start, rules), "{:?}", rules);
+        }
+    }
+}
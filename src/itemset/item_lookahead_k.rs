@@ -0,0 +1,137 @@
+use std::collections::{BTreeSet, HashMap};
+use crate::first_follow::FirstK;
+use crate::itemset::Item as _;
+use crate::syntax::{MixedChar, Rule, Terminal};
+
+/// LR(k) item, the lookahead is a string of at most k terminals
+#[derive(Hash, PartialEq, Eq, Clone, Debug, PartialOrd, Ord)]
+pub struct Item{
+    pub(crate) rule_number: usize,
+    kernel: bool,
+    dot: usize,
+    follow: Vec<Terminal>
+}
+
+impl<'display> super::Item<'display> for Item {
+    type Display = super::display::item_lookahead_k::ItemDisplay<'display>;
+
+    fn shift(&self) -> Self {
+        Self { rule_number: self.rule_number, dot: self.dot + 1, kernel: true, follow: self.follow.clone() }
+    }
+    fn symbol(&self, rules: &[Rule]) -> Option<MixedChar> {
+        rules[self.rule_number].output.data.get(self.dot).copied()
+    }
+
+    fn is_end(&self, rules: &[Rule]) -> bool {
+        rules[self.rule_number].output.data.len() == self.dot
+    }
+
+    fn display(&'display self, rules: &'display [Rule]) -> Self::Display{
+        super::display::item_lookahead_k::ItemDisplay{
+            item: self,
+            rules
+        }
+    }
+
+    fn dot(&self) -> usize {self.dot}
+
+    fn kernel(&self) -> bool {
+        self.kernel
+    }
+}
+
+impl Item {
+    pub fn new(rule_number: usize, dot: usize, follow: Vec<Terminal>, kernel: bool) -> Self {
+        Self { rule_number, dot, kernel, follow }
+    }
+
+    pub fn follow(&self) -> &[Terminal] {&self.follow}
+}
+
+#[derive(Hash, Eq, PartialEq, Clone, Debug)]
+pub struct ItemSet{
+    pub items: BTreeSet<Item>,
+    pub(crate) symbols: BTreeSet<MixedChar>,
+}
+
+impl<'item> super::ItemSet<'item> for ItemSet {
+    type Item = Item;
+    type ItemIterator = std::collections::btree_set::Iter<'item, Self::Item>;
+    fn items(&'item self) -> Self::ItemIterator { self.items.iter() }
+
+    fn reduce_reduce_conflict<'a>(&'a self, rules: &'a [Rule]) -> Vec<(&'a Rule, &'a Rule)> {
+        let mut map: HashMap<&[Terminal], &Rule> = HashMap::new();
+        let mut out = vec![];
+
+        for (rule, follow) in self.reduce(rules) {
+            if let Some(exist_rule) = map.get(follow) {
+                out.push((*exist_rule, rule));
+            }
+            map.insert(follow, rule);
+        }
+        out
+    }
+}
+
+impl ItemSet {
+    pub(crate) fn new() -> Self {
+        Self { items: BTreeSet::default(), symbols: BTreeSet::default()}
+    }
+
+    pub(crate) fn add_item(&mut self, rules: &[Rule], item: Item) -> bool {
+        if let Some(character) = item.symbol(rules){
+            self.symbols.insert(character);
+        }
+        self.items.insert(item)
+    }
+
+    /// Adds [B -> • γ, u] for every [A -> α • B β, v] and every u in FIRST_k(β v)
+    pub(crate) fn closure(&mut self, rules: &[Rule], first: &FirstK) {
+        let mut pending: Vec<Item> = self.items.iter().cloned().collect();
+        while let Some(item) = pending.pop() {
+            let Some(MixedChar::Variable(variable)) = item.symbol(rules) else {
+                continue;
+            };
+            let rest = &rules[item.rule_number].output.data[item.dot + 1..];
+            let follows = first.of(rest, &item.follow);
+            for (rule_number, _) in rules.iter().enumerate().filter(|(_, rule)| rule.clause == variable) {
+                for follow in &follows {
+                    let next = Item::new(rule_number, 0, follow.clone(), false);
+                    if self.add_item(rules, next.clone()) {
+                        pending.push(next);
+                    }
+                }
+            }
+        }
+    }
+
+    pub(crate) fn transitions(&self, transchar: MixedChar, rules: &[Rule]) -> Option<ItemSet> {
+        let mut new_set = Self::new();
+        for item in &self.items {
+            if item.symbol(rules) == Some(transchar) {
+                new_set.add_item(rules, item.shift());
+            }
+        }
+        if new_set.items.is_empty() {
+            return None;
+        }
+        Some(new_set)
+    }
+
+    pub fn reduce<'a>(&'a self, rules: &'a [Rule]) -> impl Iterator<Item = (&'a Rule, &'a [Terminal])> + 'a {
+        self.items.iter().filter_map(|item| {
+            match item.is_end(rules) {
+                true => Some((&rules[item.rule_number], item.follow())),
+                false => None,
+            }
+        })
+    }
+
+    /// Lookahead strings on which the set shifts, FIRST_k(a β v) for every [A -> α • a β, v]
+    pub fn shifts(&self, rules: &[Rule], first: &FirstK) -> BTreeSet<Vec<Terminal>> {
+        self.items.iter()
+            .filter(|item| matches!(item.symbol(rules), Some(MixedChar::Terminal(_))))
+            .flat_map(|item| first.of(&rules[item.rule_number].output.data[item.dot..], &item.follow))
+            .collect()
+    }
+}
@@ -0,0 +1,199 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use crate::first_follow;
+use crate::itemset::{Item as _, ItemSet as _};
+use crate::itemset::item_lookahead::ItemSet;
+use crate::itemset::lr_one::ItemSets;
+use crate::itemset::lr_zero::ItemSets as LRZeroItemSets;
+use crate::syntax::{MixedChar, Terminal, Variable};
+
+type Set<T> = BTreeSet<T>;
+
+/// Kernel of a state without lookahead, (rule number, dot) of every kernel item
+pub type Core = Set<(usize, usize)>;
+
+/// DeRemer and Pennello's traversal, F(x) = F'(x) ∪ ⋃{F(y) | x R y} with strongly connected parts sharing one set
+/// https://doi.org/10.1145/69622.357187
+fn digraph(relation: &[Vec<usize>], initial: Vec<Set<Terminal>>) -> Vec<Set<Terminal>> {
+    struct Traversal<'a> {
+        relation: &'a [Vec<usize>],
+        sets: Vec<Set<Terminal>>,
+        depth: Vec<usize>,
+        stack: Vec<usize>,
+    }
+    impl Traversal<'_> {
+        fn traverse(&mut self, x: usize) {
+            self.stack.push(x);
+            let d = self.stack.len();
+            self.depth[x] = d;
+            for y in self.relation[x].iter().copied() {
+                if self.depth[y] == 0 {
+                    self.traverse(y);
+                }
+                self.depth[x] = self.depth[x].min(self.depth[y]);
+                let extra = self.sets[y].clone();
+                self.sets[x].extend(extra);
+            }
+            if self.depth[x] == d {
+                while let Some(top) = self.stack.pop() {
+                    self.depth[top] = usize::MAX;
+                    self.sets[top] = self.sets[x].clone();
+                    if top == x {
+                        break;
+                    }
+                }
+            }
+        }
+    }
+    let len = relation.len();
+    let mut traversal = Traversal { relation, sets: initial, depth: vec![0; len], stack: vec![] };
+    for x in 0..len {
+        if traversal.depth[x] == 0 {
+            traversal.traverse(x);
+        }
+    }
+    traversal.sets
+}
+
+impl ItemSets {
+    /// LALR(1) item sets, lookaheads are computed on the LR(0) automaton
+    /// through the reads, includes and lookback relations
+    pub fn generate_lalr(&mut self) {
+        let mut automaton = LRZeroItemSets { rules: self.rules.clone(), sets: vec![], ordering_map: vec![] };
+        automaton.generate_next();
+        let goto: Vec<HashMap<MixedChar, usize>> = automaton.ordering_map.iter()
+            .map(|next| next.iter().copied().collect())
+            .collect();
+        let nullable = first_follow::nullable(&self.rules);
+
+        let transitions: Vec<(usize, Variable)> = goto.iter().enumerate()
+            .flat_map(|(state, next)| next.keys().filter_map(move |symbol| Some((state, Variable::try_from(symbol).ok()?))))
+            .collect();
+        let index: HashMap<(usize, Variable), usize> = transitions.iter().enumerate().map(|(id, transition)| (*transition, id)).collect();
+
+        // DR(p, A): terminals shifted right after the goto on A
+        let mut direct: Vec<Set<Terminal>> = transitions.iter()
+            .map(|(state, variable)| {
+                let target = goto[*state][&(*variable).into()];
+                goto[target].keys().filter_map(|symbol| Terminal::try_from(symbol).ok()).collect()
+            })
+            .collect();
+        if let Some(MixedChar::Variable(start)) = self.rules[0].output.data.first() {
+            if let Some(id) = index.get(&(0, *start)) {
+                direct[*id].insert(Terminal::end());
+            }
+        }
+
+        // (p, A) reads (r, C) when p --A--> r --C--> and C is nullable
+        let reads: Vec<Vec<usize>> = transitions.iter()
+            .map(|(state, variable)| {
+                let target = goto[*state][&(*variable).into()];
+                goto[target].keys()
+                    .filter_map(|symbol| Variable::try_from(symbol).ok())
+                    .filter(|next| nullable.contains(next))
+                    .map(|next| index[&(target, next)])
+                    .collect()
+            })
+            .collect();
+        let read = digraph(&reads, direct);
+
+        // (p, A) includes (p', B) when B -> βAγ, γ is nullable and p' --β--> p
+        let mut includes: Vec<Vec<usize>> = vec![vec![]; transitions.len()];
+        for (id, (state, variable)) in transitions.iter().enumerate() {
+            for rule in self.rules.iter().filter(|rule| rule.clause == *variable) {
+                let mut current = *state;
+                for (position, symbol) in rule.output.data.iter().enumerate() {
+                    let rest_nullable = rule.output.data[position + 1..].iter()
+                        .all(|rest| matches!(rest, MixedChar::Variable(rest) if nullable.contains(rest)));
+                    if let (MixedChar::Variable(inner), true) = (symbol, rest_nullable) {
+                        includes[index[&(current, *inner)]].push(id);
+                    }
+                    current = goto[current][symbol];
+                }
+            }
+        }
+        let follow = &digraph(&includes, read);
+
+        // every item [A -> α•β] reached from (p, A) shares Follow(p, A) as lookahead,
+        // which for a finished item is the lookback relation
+        let mut sets: Vec<ItemSet> = (0..automaton.sets.len()).map(|_| ItemSet::new()).collect();
+        let accept_follow = Set::from([Terminal::end()]);
+        let starts = transitions.iter().enumerate()
+            .flat_map(|(id, (state, variable))| {
+                self.rules.iter().enumerate()
+                    .filter(move |(_, rule)| rule.clause == *variable)
+                    .map(move |(rule_number, _)| (*state, rule_number, &follow[id]))
+            })
+            .chain(std::iter::once((0, 0, &accept_follow)));
+        for (state, rule_number, follows) in starts {
+            let rule = &self.rules[rule_number];
+            let mut current = state;
+            for dot in 0..=rule.len() {
+                match dot > 0 || rule_number == 0 {
+                    true => sets[current].add_kernel(rule, dot, rule_number, follows),
+                    false => sets[current].add_rule(rule, dot, rule_number, follows),
+                }
+                if let Some(symbol) = rule.output.data.get(dot) {
+                    current = goto[current][symbol];
+                }
+            }
+        }
+        self.sets = sets;
+        self.ordering_map = automaton.ordering_map;
+    }
+
+    /// Lookaheads of every state merged by core, two builders produce the same LALR(1) automaton
+    /// exactly when these maps are equal
+    pub fn merged_lookaheads(&self) -> BTreeMap<Core, Set<(usize, usize, Terminal)>> {
+        let mut merged: BTreeMap<Core, Set<(usize, usize, Terminal)>> = BTreeMap::new();
+        for set in &self.sets {
+            let core: Core = set.items().filter(|item| item.kernel()).map(|item| (item.rule_number, item.dot())).collect();
+            merged.entry(core).or_default().extend(set.items().map(|item| (item.rule_number, item.dot(), item.follow())));
+        }
+        merged
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ItemSets;
+
+    fn itemsets(start: char, rules: &[&str], generate: fn(&mut ItemSets)) -> ItemSets {
+        let mut itemsets = ItemSets::new(start);
+        for rule in rules {
+            assert!(itemsets.add_from_string(rule));
+        }
+        generate(&mut itemsets);
+        itemsets
+    }
+
+    /// The lalr(1) builder gives one state per core of the canonical lr(1) automaton, with the lookaheads
+    /// of the lr(1) states of that core
+    fn assert_matches_merged_lr_one(start: char, rules: &[&str]) {
+        let lalr = itemsets(start, rules, ItemSets::generate_lalr);
+        let canonical = itemsets(start, rules, ItemSets::generate_next);
+        let merged = canonical.merged_lookaheads();
+        assert_eq!(lalr.sets.len(), merged.len());
+        assert_eq!(lalr.merged_lookaheads(), merged);
+    }
+
+    #[test]
+    fn dragon_book_grammar() {
+        assert_matches_merged_lr_one('S', &["S:CC", "C:cC", "C:d"]);
+    }
+
+    #[test]
+    fn assignment_grammar() {
+        assert_matches_merged_lr_one('S', &["S:L=R", "S:R", "L:*R", "L:i", "R:L"]);
+    }
+
+    #[test]
+    fn ambiguous_expression_grammar() {
+        assert_matches_merged_lr_one('E', &["E:E+E", "E:E*E", "E:i"]);
+    }
+
+    #[test]
+    fn expression_grammar_with_empty_rules() {
+        assert_matches_merged_lr_one('E', &["E:TA", "A:+TA", "A:", "T:FB", "B:*FB", "B:", "F:(E)", "F:i"]);
+    }
+}
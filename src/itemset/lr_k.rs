@@ -0,0 +1,76 @@
+use std::collections::HashMap;
+use crate::first_follow::FirstK;
+use crate::syntax;
+use crate::itemset::item_lookahead_k::{Item, ItemSet};
+use crate::syntax::{MixedChar, Rule};
+
+/// LR(k) item sets, `LROneItemSets` with lookahead strings of up to k terminals
+pub struct ItemSets {
+    pub k: usize,
+    pub sets: Vec<ItemSet>,
+    pub rules: Vec<Rule>,
+    pub ordering_map: Vec<Vec<(MixedChar, usize)>>,
+}
+
+impl super::ItemSets<'_> for ItemSets {
+    type Item = Item;
+    type ItemSet = ItemSet;
+    fn item_sets(&self) -> &[Self::ItemSet] { &self.sets }
+    fn rules(&self) -> &[Rule] { &self.rules }
+    fn ordering_map(&self) -> &[Vec<(MixedChar, usize)>] {
+        self.ordering_map.as_slice()
+    }
+}
+
+impl ItemSets {
+    pub fn new(last_variable: char, k: usize) -> Self {
+        let mut first_rule = Rule::new(syntax::END_VARIABLE);
+        first_rule.add_variable(last_variable);
+        Self { k: k.max(1), rules: vec![first_rule], sets: Vec::default(), ordering_map: Vec::default() }
+    }
+    pub fn add_rule(&mut self, rule: Rule) {
+        self.rules.push(rule);
+    }
+
+    pub fn add_from_string(&mut self, string_rule: &str) -> bool{
+        let Ok(rule) = string_rule.try_into() else {
+            return false
+        };
+        self.rules.push(rule);
+        true
+    }
+
+    pub fn clear(&mut self) {
+        self.sets = Vec::default();
+        self.ordering_map = Vec::default();
+    }
+
+    pub fn generate_next(&mut self) {
+        let first = FirstK::from_rule(&self.rules, self.k);
+        let mut itemmaps = HashMap::new();
+        let mut first_item = ItemSet::new();
+        first_item.add_item(&self.rules, Item::new(0, 0, vec![syntax::Terminal::end()], true));
+        first_item.closure(&self.rules, &first);
+        itemmaps.insert(first_item.clone(), 0);
+        self.sets.push(first_item);
+        let mut index = 0;
+        while let Some(cur_item) = self.sets.get(index).cloned() {
+            let mut next_val = Vec::new();
+            for transition_char in cur_item.symbols.iter() {
+                let Some(mut new_itemset) = cur_item.transitions(*transition_char, &self.rules) else {
+                    continue;
+                };
+                new_itemset.closure(&self.rules, &first);
+                if let Some(new_index) = itemmaps.get(&new_itemset) {
+                    next_val.push((*transition_char, *new_index));
+                    continue;
+                }
+                itemmaps.insert(new_itemset.clone(), self.sets.len());
+                next_val.push((*transition_char, self.sets.len()));
+                self.sets.push(new_itemset);
+            }
+            self.ordering_map.push(next_val);
+            index += 1;
+        }
+    }
+}
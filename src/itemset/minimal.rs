@@ -0,0 +1,181 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use crate::itemset::Item as _;
+use crate::itemset::item_lookahead::{Item, ItemSet};
+use crate::itemset::lr_one::ItemSets;
+use crate::syntax::{MixedChar, Rule, Terminal};
+
+/// Union-find over state numbers, cloned so a merge can be tried and thrown away
+#[derive(Clone)]
+struct Groups {
+    parent: Vec<usize>,
+}
+
+impl Groups {
+    fn find(&mut self, mut state: usize) -> usize {
+        while self.parent[state] != state {
+            self.parent[state] = self.parent[self.parent[state]];
+            state = self.parent[state];
+        }
+        state
+    }
+
+    /// Joins both groups, the smaller state number stays the root
+    fn union(&mut self, left: usize, right: usize) -> bool {
+        let (left, right) = (self.find(left), self.find(right));
+        if left == right {
+            return false;
+        }
+        self.parent[left.max(right)] = left.min(right);
+        true
+    }
+}
+
+/// Terminals on which the items have more than one action
+fn conflicting_terminals<'a>(items: impl Iterator<Item = &'a Item>, rules: &[Rule]) -> BTreeSet<Terminal> {
+    let mut shifts = BTreeSet::new();
+    let mut reduces: HashMap<Terminal, BTreeSet<usize>> = HashMap::new();
+    for item in items {
+        match item.symbol(rules) {
+            Some(MixedChar::Terminal(terminal)) => {
+                shifts.insert(terminal);
+            }
+            Some(MixedChar::Variable(_)) => {}
+            None => {
+                reduces.entry(item.follow()).or_default().insert(item.rule_number);
+            }
+        }
+    }
+    reduces.into_iter()
+        .filter(|(terminal, rules)| rules.len() + shifts.contains(terminal) as usize > 1)
+        .map(|(terminal, _)| terminal)
+        .collect()
+}
+
+fn core(set: &ItemSet) -> BTreeSet<(usize, usize)> {
+    set.items.iter().filter(|item| item.kernel()).map(|item| (item.rule_number, item.dot())).collect()
+}
+
+impl ItemSets {
+    /// LR(1) item sets where states sharing a core are merged as long as the merge adds no conflict,
+    /// the parser keeps the power of LR(1) at close to the size of LALR(1)
+    /// https://doi.org/10.1007/BF00290336
+    pub fn generate_minimal(&mut self) {
+        self.generate_next();
+        let goto: Vec<HashMap<MixedChar, usize>> = self.ordering_map.iter()
+            .map(|next| next.iter().copied().collect())
+            .collect();
+        let conflicts: Vec<BTreeSet<Terminal>> = self.sets.iter()
+            .map(|set| conflicting_terminals(set.items.iter(), &self.rules))
+            .collect();
+        let mut groups = Groups { parent: (0..self.sets.len()).collect() };
+        let mut by_core: BTreeMap<BTreeSet<(usize, usize)>, Vec<usize>> = BTreeMap::new();
+        for (state, set) in self.sets.iter().enumerate() {
+            let candidates = by_core.entry(core(set)).or_default();
+            for candidate in candidates.iter() {
+                if let Some(merged) = self.try_merge(&groups, &goto, &conflicts, state, *candidate) {
+                    groups = merged;
+                    break;
+                }
+            }
+            candidates.push(state);
+        }
+
+        // groups are numbered by their smallest state so the start state stays 0
+        let mut number = HashMap::new();
+        let mut sets: Vec<ItemSet> = vec![];
+        for state in 0..self.sets.len() {
+            let root = groups.find(state);
+            let id = *number.entry(root).or_insert_with(|| {
+                sets.push(ItemSet::new());
+                sets.len() - 1
+            });
+            sets[id].merge(self.sets[state].clone());
+        }
+        let mut ordering_map = vec![vec![]; sets.len()];
+        for (state, next) in self.ordering_map.iter().enumerate() {
+            let id = number[&groups.find(state)];
+            for (symbol, target) in next {
+                let target = number[&groups.find(*target)];
+                if !ordering_map[id].contains(&(*symbol, target)) {
+                    ordering_map[id].push((*symbol, target));
+                }
+            }
+        }
+        self.sets = sets;
+        self.ordering_map = ordering_map;
+    }
+
+    /// Merges both states along with every pair of successors the merge forces together,
+    /// `None` when one of the merged groups gets a conflict none of its states had
+    fn try_merge(&self, groups: &Groups, goto: &[HashMap<MixedChar, usize>], conflicts: &[BTreeSet<Terminal>], left: usize, right: usize) -> Option<Groups> {
+        let mut groups = groups.clone();
+        let mut pending = vec![(left, right)];
+        let mut touched = vec![];
+        while let Some((left, right)) = pending.pop() {
+            if !groups.union(left, right) {
+                continue;
+            }
+            touched.push(left);
+            for (symbol, next) in &goto[left] {
+                let other = goto[right].get(symbol)?;
+                pending.push((*next, *other));
+            }
+        }
+        let mut members: HashMap<usize, Vec<usize>> = HashMap::new();
+        for state in 0..self.sets.len() {
+            members.entry(groups.find(state)).or_default().push(state);
+        }
+        for state in touched {
+            let states = &members[&groups.find(state)];
+            let allowed: BTreeSet<Terminal> = states.iter().flat_map(|state| conflicts[*state].iter().copied()).collect();
+            let merged = conflicting_terminals(states.iter().flat_map(|state| self.sets[*state].items.iter()), &self.rules);
+            if !merged.is_subset(&allowed) {
+                return None;
+            }
+        }
+        Some(groups)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::itemset::lr_one::ItemSets;
+    use crate::parsing_table::{lr_one, IStateMachine};
+
+    fn itemsets(rules: &[&str], generate: fn(&mut ItemSets)) -> ItemSets {
+        let mut itemsets = ItemSets::new('S');
+        for rule in rules {
+            assert!(itemsets.add_from_string(rule));
+        }
+        generate(&mut itemsets);
+        itemsets
+    }
+
+    fn conflicts(itemsets: &ItemSets) -> usize {
+        lr_one::StateMachine::from_itemset(itemsets).conflicts().len()
+    }
+
+    #[test]
+    fn lr_one_grammar_that_is_not_lalr() {
+        // merging the states after ac and bc gives a reduce-reduce conflict, the f C C part has
+        // states lalr(1) can merge freely
+        let rules = ["S:aAd", "S:bBd", "S:aBe", "S:bAe", "A:c", "B:c", "S:fCC", "C:gC", "C:h"];
+        let minimal = itemsets(&rules, ItemSets::generate_minimal);
+        let canonical = itemsets(&rules, ItemSets::generate_next);
+        let lalr = itemsets(&rules, ItemSets::generate_lalr);
+        assert!(conflicts(&lalr) > 0);
+        assert_eq!((conflicts(&minimal), conflicts(&canonical)), (0, 0));
+        assert!(minimal.sets.len() < canonical.sets.len());
+        assert_eq!(minimal.sets.len(), lalr.sets.len() + 1);
+    }
+
+    #[test]
+    fn lalr_grammar() {
+        for rules in [&["S:CC", "C:cC", "C:d"][..], &["S:L=R", "S:R", "L:*R", "L:i", "R:L"]] {
+            let (minimal, lalr) = (itemsets(rules, ItemSets::generate_minimal), itemsets(rules, ItemSets::generate_lalr));
+            assert_eq!(minimal.sets.len(), lalr.sets.len());
+            assert_eq!(minimal.merged_lookaheads(), lalr.merged_lookaheads());
+        }
+    }
+}
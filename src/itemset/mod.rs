@@ -1,15 +1,24 @@
 mod lr_one;
 mod lr_zero;
+mod lalr;
+mod minimal;
+mod lr_k;
+mod incremental;
+mod fast;
+mod parallel;
 
 mod item_no_lookahead;
 mod item_lookahead;
+mod item_lookahead_k;
 
 mod display;
 
 use core::fmt;
 
+pub use display::DOT;
 pub use lr_one::ItemSets as LROneItemSets;
 pub use lr_zero::ItemSets as LRZeroItemSets;
+pub use lr_k::ItemSets as LRkItemSets;
 use crate::syntax::{MixedChar, Rule};
 
 pub trait Item <'display>{
@@ -0,0 +1,82 @@
+use std::thread;
+
+use crate::itemset::fast::{Closure, Grammar, Kernel, Kernels};
+use crate::itemset::item_lookahead::ItemSet;
+use crate::itemset::lr_one::ItemSets;
+use crate::syntax::MixedChar;
+
+/// `work` on every item of `items`, split into one chunk per thread, the results in the order of the items
+fn split<T: Sync, R: Send>(items: &[T], threads: usize, work: impl Fn(&T) -> R + Sync) -> Vec<R> {
+    let chunk = items.len().div_ceil(threads.max(1)).max(1);
+    let work = &work;
+    thread::scope(|scope| {
+        let handles: Vec<_> = items.chunks(chunk)
+            .map(|chunk| scope.spawn(move || chunk.iter().map(work).collect::<Vec<R>>()))
+            .collect();
+        handles.into_iter().flat_map(|handle| handle.join().unwrap()).collect()
+    })
+}
+
+impl ItemSets {
+    /// Same item sets and numbering as `generate_next`, built like `generate_fast` on interned kernels.
+    /// The closure of every item and then the states found in the last round are computed on `threads`
+    /// scoped threads, the new kernels are numbered afterwards in state order
+    pub fn generate_parallel(&mut self, threads: usize) {
+        let grammar = Grammar::new(&self.rules);
+        let items: Vec<usize> = (0..grammar.item_count()).collect();
+        let closures: Vec<Option<Closure>> = split(&items, threads, |item| Some(grammar.closure_of(*item)));
+        let mut kernels = Kernels::new(grammar.start());
+        let mut sets = vec![];
+        let mut ordering_map = vec![];
+
+        let mut frontier = 0..1;
+        while !frontier.is_empty() {
+            let expanded: Vec<(ItemSet, Vec<(MixedChar, Kernel)>)> =
+                split(&kernels.kernels[frontier.clone()], threads, |kernel| grammar.expand(kernel, &closures));
+            let end = kernels.kernels.len();
+            for (set, successors) in expanded {
+                ordering_map.push(kernels.number(successors));
+                sets.push(set);
+            }
+            frontier = end..kernels.kernels.len();
+        }
+        self.sets = sets;
+        self.ordering_map = ordering_map;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::generate;
+    use crate::itemset::lr_one::ItemSets;
+    use crate::syntax::Rule;
+
+    fn itemsets(rules: &[Rule], generate: impl Fn(&mut ItemSets)) -> ItemSets {
+        let mut itemsets = ItemSets::new('E');
+        for rule in rules {
+            itemsets.add_rule(rule.clone());
+        }
+        generate(&mut itemsets);
+        itemsets
+    }
+
+    fn assert_same_as_generate_next(rules: &[Rule]) {
+        let slow = itemsets(rules, ItemSets::generate_next);
+        for threads in [1, 3, 8] {
+            let parallel = itemsets(rules, |itemsets| itemsets.generate_parallel(threads));
+            assert_eq!(slow.sets, parallel.sets);
+            assert_eq!(slow.ordering_map, parallel.ordering_map);
+        }
+    }
+
+    #[test]
+    fn benchmark_grammar() {
+        assert_same_as_generate_next(&generate::benchmark_grammar(6, 4));
+    }
+
+    #[test]
+    fn grammar_with_empty_rules() {
+        let rules = ["E:TA", "A:+TA", "A:", "T:FB", "B:*FB", "B:", "F:(E)", "F:i"];
+        assert_same_as_generate_next(&rules.map(|rule| Rule::try_from(rule).unwrap()));
+    }
+}
@@ -0,0 +1,141 @@
+use std::collections::{BTreeSet, HashMap};
+
+use crate::lexer::nfa::Nfa;
+
+/// Class of a state, the definition it accepts and the classes of the states it moves to
+type Signature = (usize, Option<usize>, Vec<Option<usize>>);
+
+/// Deterministic automaton over classes of characters, class `i` holds the code points
+/// from `boundaries[i]` up to `boundaries[i + 1]`, every character of a class moves the same way
+pub struct Dfa {
+    pub boundaries: Vec<u32>,
+    pub transitions: Vec<Vec<Option<usize>>>,
+    /// definition matched when the input ends in the state
+    pub accept: Vec<Option<usize>>,
+}
+
+impl Dfa {
+    /// Subset construction, every state is the epsilon closed set of nfa states it stands for
+    pub fn from_nfa(nfa: &Nfa) -> Self {
+        let boundaries: BTreeSet<u32> = nfa.states.iter()
+            .flat_map(|state| state.transitions.iter().flat_map(|(low, high, _)| [*low, high + 1]))
+            .collect();
+        let boundaries: Vec<u32> = boundaries.into_iter().collect();
+        let classes = boundaries.len().saturating_sub(1);
+
+        let mut start = BTreeSet::from([nfa.start]);
+        nfa.epsilon_closure(&mut start);
+        let mut sets = vec![start.clone()];
+        let mut index: HashMap<BTreeSet<usize>, usize> = HashMap::from([(start, 0)]);
+        let mut transitions = vec![];
+        let mut current = 0;
+        while current < sets.len() {
+            let mut row = vec![None; classes];
+            for (class, next) in row.iter_mut().enumerate() {
+                let (low, high) = (boundaries[class], boundaries[class + 1] - 1);
+                let mut moved: BTreeSet<usize> = sets[current].iter()
+                    .flat_map(|state| nfa.states[*state].transitions.iter())
+                    .filter(|(range_low, range_high, _)| *range_low <= low && high <= *range_high)
+                    .map(|(_, _, target)| *target)
+                    .collect();
+                if moved.is_empty() {
+                    continue;
+                }
+                nfa.epsilon_closure(&mut moved);
+                *next = Some(*index.entry(moved).or_insert_with_key(|moved| {
+                    sets.push(moved.clone());
+                    sets.len() - 1
+                }));
+            }
+            transitions.push(row);
+            current += 1;
+        }
+        // the definition written first wins when a state ends several
+        let accept = sets.iter()
+            .map(|set| set.iter().filter_map(|state| nfa.states[*state].accept).min())
+            .collect();
+        Self { boundaries, transitions, accept }
+    }
+
+    pub fn len(&self) -> usize {
+        self.transitions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.transitions.is_empty()
+    }
+
+    /// Merges states accepting the same definition that can not be told apart by any input,
+    /// classes are split until every move agrees, as in Moore's algorithm
+    pub fn minimized(&self) -> Self {
+        let mut class: Vec<usize> = vec![0; self.len()];
+        let mut count = 1;
+        loop {
+            let mut number: HashMap<Signature, usize> = HashMap::new();
+            let next: Vec<usize> = (0..self.len())
+                .map(|state| {
+                    let moves = self.transitions[state].iter().map(|next| next.map(|next| class[next])).collect();
+                    let id = number.len();
+                    *number.entry((class[state], self.accept[state], moves)).or_insert(id)
+                })
+                .collect();
+            class = next;
+            if number.len() == count {
+                break;
+            }
+            count = number.len();
+        }
+
+        let mut transitions = vec![vec![]; count];
+        let mut accept = vec![None; count];
+        for (state, id) in class.iter().enumerate() {
+            transitions[*id] = self.transitions[state].iter().map(|next| next.map(|next| class[next])).collect();
+            accept[*id] = self.accept[state];
+        }
+        Self { boundaries: self.boundaries.clone(), transitions, accept }
+    }
+
+    /// State reached from `state` on `symbol`
+    pub fn step(&self, state: usize, symbol: char) -> Option<usize> {
+        let symbol = symbol as u32;
+        let class = self.boundaries.partition_point(|boundary| *boundary <= symbol).checked_sub(1)?;
+        self.transitions[state].get(class).copied().flatten()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Dfa;
+    use crate::lexer::nfa::Nfa;
+    use crate::lexer::regex::Regex;
+
+    fn dfa(patterns: &[&str]) -> Dfa {
+        let patterns: Vec<Regex> = patterns.iter().map(|pattern| Regex::parse(pattern).unwrap()).collect();
+        Dfa::from_nfa(&Nfa::new(&patterns))
+    }
+
+    fn run(dfa: &Dfa, text: &str) -> Option<usize> {
+        let state = text.chars().try_fold(0, |state, symbol| dfa.step(state, symbol))?;
+        dfa.accept[state]
+    }
+
+    #[test]
+    fn minimized_state_count() {
+        // the subset construction of the dragon book example has five states, the minimal automaton four
+        let dfa = dfa(&["(a|b)*abb"]);
+        let minimized = dfa.minimized();
+        assert_eq!((dfa.len(), minimized.len()), (5, 4));
+        for text in ["abb", "aabb", "babb", "abababb", "ab", "abba", ""] {
+            assert_eq!(run(&minimized, text), run(&dfa, text), "{:?}", text);
+        }
+        assert_eq!(run(&minimized, "babb"), Some(0));
+        assert_eq!(run(&minimized, "abba"), None);
+    }
+
+    #[test]
+    fn states_of_different_definitions_stay_apart() {
+        let dfa = dfa(&["a", "b"]).minimized();
+        assert_eq!(dfa.len(), 3);
+        assert_eq!((run(&dfa, "a"), run(&dfa, "b"), run(&dfa, "c")), (Some(0), Some(1), None));
+    }
+}
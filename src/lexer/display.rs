@@ -0,0 +1,82 @@
+use std::fmt::{Display, Formatter};
+
+use prettytable::{Cell, Row, Table};
+
+use crate::lexer::{DefinitionError, LexError, Lexer, Location, Span, Token};
+
+/// A code point, escaped when it does not print
+fn code_point(point: u32) -> String {
+    match char::from_u32(point) {
+        Some(symbol) if symbol.is_ascii_graphic() || (symbol.is_alphanumeric() && !symbol.is_ascii()) => symbol.to_string(),
+        _ => format!("\\u{{{:x}}}", point),
+    }
+}
+
+impl Display for Location {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+impl Display for Span {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}..{}", self.start, self.end)
+    }
+}
+
+impl Display for Token {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {:?} {}", self.terminal, self.text, self.span)
+    }
+}
+
+impl Display for LexError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: no token starts with {:?}", self.location, self.symbol)
+    }
+}
+
+impl Display for DefinitionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {} column {}: {}", self.line, self.column + 1, self.message)
+    }
+}
+
+impl Display for Lexer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for definition in &self.definitions {
+            match definition.terminal {
+                Some(terminal) => writeln!(f, "{:6}{}", terminal.to_string(), definition.pattern)?,
+                None => writeln!(f, "{:6}{}", "skip", definition.pattern)?,
+            }
+        }
+        writeln!(f, "{} states, {} before minimization", self.dfa.len(), self.unminimized)?;
+        let mut table = Table::new();
+        table.set_titles(Row::new(vec![Cell::new("State"), Cell::new("Token"), Cell::new("Moves")]));
+        for (state, row) in self.dfa.transitions.iter().enumerate() {
+            let token = match self.dfa.accept[state].map(|definition| self.definitions[definition].terminal) {
+                Some(Some(terminal)) => terminal.to_string(),
+                Some(None) => "skip".to_string(),
+                None => String::new(),
+            };
+            // neighbouring classes going to the same state are shown as one range
+            let mut ranges: Vec<(u32, u32, usize)> = vec![];
+            for (class, next) in row.iter().enumerate() {
+                let (low, high) = (self.dfa.boundaries[class], self.dfa.boundaries[class + 1] - 1);
+                match (ranges.last_mut(), next) {
+                    (Some(last), Some(next)) if last.1 + 1 == low && last.2 == *next => last.1 = high,
+                    (_, Some(next)) => ranges.push((low, high, *next)),
+                    (_, None) => {}
+                }
+            }
+            let moves: Vec<String> = ranges.iter()
+                .map(|(low, high, next)| match low == high {
+                    true => format!("{} -> {}", code_point(*low), next),
+                    false => format!("{}-{} -> {}", code_point(*low), code_point(*high), next),
+                })
+                .collect();
+            table.add_row(Row::new(vec![Cell::new(&state.to_string()), Cell::new(&token), Cell::new(&moves.join("\n"))]));
+        }
+        write!(f, "{}", table)
+    }
+}
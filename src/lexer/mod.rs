@@ -0,0 +1,329 @@
+mod display;
+pub mod regex;
+pub mod nfa;
+pub mod dfa;
+
+use std::collections::{BTreeSet, VecDeque};
+
+use crate::lexer::dfa::Dfa;
+use crate::lexer::nfa::Nfa;
+use crate::lexer::regex::Regex;
+use crate::syntax::{MixedChar, Rule, Terminal};
+
+/// A point of the source, the byte offset and the line and column counted from 1, columns count characters
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Location {
+    pub offset: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Default for Location {
+    fn default() -> Self {
+        Self { offset: 0, line: 1, column: 1 }
+    }
+}
+
+impl Location {
+    /// The location after `symbol`
+    pub fn advance(self, symbol: char) -> Self {
+        match symbol {
+            '\n' => Self { offset: self.offset + 1, line: self.line + 1, column: 1 },
+            symbol => Self { offset: self.offset + symbol.len_utf8(), column: self.column + 1, ..self },
+        }
+    }
+
+    /// This location of a piece of a source taken as a location of the whole source, the piece starts at `start`
+    pub fn within(self, start: Location) -> Self {
+        match self.line {
+            1 => Self { offset: start.offset + self.offset, line: start.line, column: start.column + self.column - 1 },
+            line => Self { offset: start.offset + self.offset, line: start.line + line - 1, column: self.column },
+        }
+    }
+
+    /// Location before every character of `input` and after the last one
+    pub fn of_chars(input: &[char]) -> Vec<Self> {
+        let mut locations = vec![Self::default()];
+        for symbol in input {
+            locations.push(locations.last().unwrap().advance(*symbol));
+        }
+        locations
+    }
+}
+
+/// Part of the source from `start` up to `end`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+pub struct Span {
+    pub start: Location,
+    pub end: Location,
+}
+
+impl Span {
+    /// Empty span, where a nonterminal deriving nothing or the end of input sits
+    pub fn point(location: Location) -> Self {
+        Self { start: location, end: location }
+    }
+
+    /// From the start of this span to the end of `other`
+    pub fn cover(&self, other: &Span) -> Self {
+        Self { start: self.start, end: other.end }
+    }
+
+    pub fn within(&self, start: Location) -> Self {
+        Self { start: self.start.within(start), end: self.end.within(start) }
+    }
+
+    /// Byte range of the span
+    pub fn range(&self) -> std::ops::Range<usize> {
+        self.start.offset..self.end.offset
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Token {
+    pub terminal: Terminal,
+    pub span: Span,
+    pub text: String,
+}
+
+/// A line `%i [a-z]+` of the grammar file, the terminal `i` is any match of the pattern,
+/// `%skip [ \t]+` drops what it matches
+#[derive(Clone, Debug)]
+pub struct Definition {
+    /// `None` for skipped input
+    pub terminal: Option<Terminal>,
+    pub pattern: String,
+}
+
+#[derive(Debug)]
+pub struct DefinitionError {
+    pub line: usize,
+    pub column: usize,
+    pub message: &'static str,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct LexError {
+    pub location: Location,
+    pub symbol: char,
+}
+
+/// Splits the input into the longest match of any definition, the earlier definition wins a tie
+pub struct Lexer {
+    pub definitions: Vec<Definition>,
+    pub dfa: Dfa,
+    /// states before minimization
+    pub unminimized: usize,
+}
+
+impl Definition {
+    /// Definition of a `%` line, `None` for the lines holding rules
+    pub fn parse(line: &str) -> Option<Result<Self, (usize, &'static str)>> {
+        let definition = line.strip_prefix('%')?;
+        let Some((name, pattern)) = definition.split_once(' ') else {
+            return Some(Err((line.len(), "definition without a pattern")));
+        };
+        let mut symbols = name.chars();
+        let terminal = match (name, symbols.next(), symbols.next()) {
+            ("skip", _, _) => None,
+            (_, Some(symbol), None) => Some(Terminal { symbol }),
+            _ => return Some(Err((1, "a token is named by a single character or skip"))),
+        };
+        Some(Ok(Self { terminal, pattern: pattern.to_string() }))
+    }
+
+    /// The terminal matching only itself, for terminals of the rules without a definition
+    fn literal(terminal: Terminal) -> Self {
+        let pattern = match "\\|*+?()[].".contains(terminal.symbol) {
+            true => format!("\\{}", terminal.symbol),
+            false => terminal.symbol.to_string(),
+        };
+        Self { terminal: Some(terminal), pattern }
+    }
+}
+
+impl Lexer {
+    /// Lexer of the `%` lines of a grammar file, `None` when there are none and every character is a terminal
+    pub fn from_source(source: &str, rules: &[Rule]) -> Option<Result<Self, DefinitionError>> {
+        let mut definitions = vec![];
+        let mut lines = vec![];
+        for (line, text) in source.lines().enumerate() {
+            match Definition::parse(text) {
+                Some(Ok(definition)) => {
+                    definitions.push(definition);
+                    lines.push(line + 1);
+                }
+                Some(Err((column, message))) => return Some(Err(DefinitionError { line: line + 1, column, message })),
+                None => {}
+            }
+        }
+        if definitions.is_empty() {
+            return None;
+        }
+        Some(Self::new(definitions, rules).map_err(|(definition, column, message)| {
+            // the pattern starts after the name and its space
+            let name = lines[definition];
+            let text = source.lines().nth(name - 1).unwrap_or_default();
+            DefinitionError { line: name, column: column + text.find(' ').map_or(0, |space| space + 1), message }
+        }))
+    }
+
+    /// Terminals of the rules without a definition of their own are added as literals after `definitions`,
+    /// a bad pattern gives its definition and the position in the pattern
+    pub fn new(mut definitions: Vec<Definition>, rules: &[Rule]) -> Result<Self, (usize, usize, &'static str)> {
+        let defined: BTreeSet<Terminal> = definitions.iter().filter_map(|definition| definition.terminal).collect();
+        let used: BTreeSet<Terminal> = rules.iter()
+            .flat_map(|rule| rule.output.data.iter())
+            .filter_map(|symbol| match symbol {
+                MixedChar::Terminal(terminal) => Some(*terminal),
+                MixedChar::Variable(_) => None,
+            })
+            .collect();
+        definitions.extend(used.difference(&defined).map(|terminal| Definition::literal(*terminal)));
+
+        let patterns = definitions.iter().enumerate()
+            .map(|(index, definition)| Regex::parse(&definition.pattern).map_err(|(column, message)| (index, column, message)))
+            .collect::<Result<Vec<Regex>, _>>()?;
+        let dfa = Dfa::from_nfa(&Nfa::new(&patterns));
+        Ok(Self { definitions, unminimized: dfa.len(), dfa: dfa.minimized() })
+    }
+
+    /// Tokens pulled from `chars` only when asked for, characters are read up to the end of the longest match
+    /// and the ones read past it are kept for the next token
+    pub fn stream<I: Iterator<Item = char>>(&self, chars: I) -> TokenStream<'_, I> {
+        TokenStream { lexer: self, chars, buffer: VecDeque::new(), location: Location::default(), failed: false }
+    }
+
+    /// Tokens of the input without the skipped ones
+    pub fn tokens(&self, input: &str) -> Result<Vec<Token>, LexError> {
+        self.stream(input.chars()).collect()
+    }
+
+    /// Tokens of every line lexed on its own, so a line break ends a token and needs no definition.
+    /// Locations are within the whole text and every line is read when the tokens before it are used up
+    pub fn lines<'lexer, I>(&'lexer self, lines: I) -> impl Iterator<Item = Result<Token, LexError>> + 'lexer
+        where I: Iterator<Item = String> + 'lexer {
+        lines
+            .scan(Location::default(), move |next, line| {
+                let start = *next;
+                *next = line.chars().chain(['\n']).fold(start, Location::advance);
+                let tokens = self.stream(line.chars().collect::<Vec<char>>().into_iter()).map(move |token| match token {
+                    Ok(token) => Ok(Token { span: token.span.within(start), ..token }),
+                    Err(error) => Err(LexError { location: error.location.within(start), ..error }),
+                });
+                Some(tokens)
+            })
+            .flatten()
+    }
+}
+
+/// Every character a token of its own terminal, for grammars without definitions, line breaks are skipped
+pub fn characters<I: Iterator<Item = char>>(chars: I) -> impl Iterator<Item = Token> {
+    chars
+        .scan(Location::default(), |location, symbol| {
+            let start = *location;
+            *location = start.advance(symbol);
+            Some(Token { terminal: Terminal { symbol }, span: Span { start, end: *location }, text: symbol.to_string() })
+        })
+        .filter(|token| !matches!(token.terminal.symbol, '\n' | '\r'))
+}
+
+pub struct TokenStream<'lexer, I: Iterator<Item = char>> {
+    lexer: &'lexer Lexer,
+    chars: I,
+    /// characters read but not matched yet
+    buffer: VecDeque<char>,
+    location: Location,
+    /// ends the stream after an error
+    failed: bool,
+}
+
+impl<I: Iterator<Item = char>> TokenStream<'_, I> {
+    /// Length of the longest match at the start of the buffer and the definition it matches
+    fn longest_match(&mut self) -> Option<(usize, usize)> {
+        let dfa = &self.lexer.dfa;
+        let mut state = 0;
+        let mut found = None;
+        for length in 1.. {
+            if self.buffer.len() < length {
+                self.buffer.extend(self.chars.next());
+            }
+            let Some(next) = self.buffer.get(length - 1).and_then(|symbol| dfa.step(state, *symbol)) else {
+                break;
+            };
+            state = next;
+            if let Some(definition) = dfa.accept[state] {
+                found = Some((length, definition));
+            }
+        }
+        found
+    }
+}
+
+impl<I: Iterator<Item = char>> Iterator for TokenStream<'_, I> {
+    type Item = Result<Token, LexError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.failed {
+            if self.buffer.is_empty() {
+                self.buffer.extend(self.chars.next());
+            }
+            let symbol = *self.buffer.front()?;
+            let Some((length, definition)) = self.longest_match() else {
+                self.failed = true;
+                return Some(Err(LexError { location: self.location, symbol }));
+            };
+            let start = self.location;
+            let text: String = self.buffer.drain(..length).collect();
+            self.location = text.chars().fold(start, |location, symbol| location.advance(symbol));
+            if let Some(terminal) = self.lexer.definitions[definition].terminal {
+                return Some(Ok(Token { terminal, span: Span { start, end: self.location }, text }));
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Lexer, LexError, Location};
+    use crate::syntax::tests::rules;
+
+    fn lexer(source: &str) -> Lexer {
+        let lines: Vec<&str> = source.lines().filter(|line| !line.starts_with('%')).collect();
+        Lexer::from_source(source, &rules(&lines)).unwrap().unwrap()
+    }
+
+    fn tokens(lexer: &Lexer, input: &str) -> Vec<(char, String)> {
+        lexer.tokens(input).unwrap().into_iter().map(|token| (token.terminal.symbol, token.text)).collect()
+    }
+
+    #[test]
+    fn longest_match_and_the_first_definition_on_a_tie() {
+        let lexer = lexer("%f if\n%i [a-z]+\n%skip [ ]+\nE:fi");
+        let expected = [('f', "if"), ('i', "iff"), ('i', "i"), ('f', "if")].map(|(terminal, text)| (terminal, text.to_string()));
+        assert_eq!(tokens(&lexer, "if iff i if"), expected);
+    }
+
+    #[test]
+    fn terminals_without_a_definition_match_themselves() {
+        let lexer = lexer("%n \\d+\nE:E+n\nE:(E)\nE:n");
+        assert_eq!(tokens(&lexer, "(12+3)").iter().map(|(terminal, _)| *terminal).collect::<String>(), "(n+n)");
+        assert_eq!(lexer.tokens("1+x").unwrap_err(), LexError { location: Location { offset: 2, line: 1, column: 3 }, symbol: 'x' });
+    }
+
+    #[test]
+    fn definition_errors_point_into_the_line() {
+        for (source, (line, column, message)) in [
+            ("E:i\n%i [a-", (2, 3, "unclosed [")),
+            ("%i ab)", (1, 5, "unmatched )")),
+            ("%ii a", (1, 1, "a token is named by a single character or skip")),
+            ("E:i\n\n%i", (3, 2, "definition without a pattern")),
+        ] {
+            let Some(Err(error)) = Lexer::from_source(source, &[]) else {
+                panic!("{:?} has no bad definition", source);
+            };
+            assert_eq!((error.line, error.column, error.message), (line, column, message), "{:?}", source);
+        }
+    }
+}
@@ -0,0 +1,121 @@
+use std::collections::BTreeSet;
+
+use crate::lexer::regex::Regex;
+
+#[derive(Default)]
+pub struct NfaState {
+    pub epsilon: Vec<usize>,
+    /// inclusive range of code points and the state it leads to
+    pub transitions: Vec<(u32, u32, usize)>,
+    /// definition matched when the input ends here
+    pub accept: Option<usize>,
+}
+
+/// Thompson's construction, one fragment with a single start and end per part of the expression
+/// https://doi.org/10.1145/363347.363387
+pub struct Nfa {
+    pub states: Vec<NfaState>,
+    pub start: usize,
+}
+
+impl Nfa {
+    /// One automaton for all patterns, the end of pattern `i` accepts definition `i`
+    pub fn new(patterns: &[Regex]) -> Self {
+        let mut nfa = Self { states: vec![NfaState::default()], start: 0 };
+        for (definition, pattern) in patterns.iter().enumerate() {
+            let (start, end) = nfa.build(pattern);
+            nfa.states[0].epsilon.push(start);
+            nfa.states[end].accept = Some(definition);
+        }
+        nfa
+    }
+
+    fn add_state(&mut self) -> usize {
+        self.states.push(NfaState::default());
+        self.states.len() - 1
+    }
+
+    /// Start and end state of the fragment of `regex`
+    fn build(&mut self, regex: &Regex) -> (usize, usize) {
+        let (start, end) = (self.add_state(), self.add_state());
+        match regex {
+            Regex::Empty => self.states[start].epsilon.push(end),
+            Regex::Class(ranges) => {
+                self.states[start].transitions = ranges.iter().map(|(low, high)| (*low, *high, end)).collect();
+            }
+            Regex::Concat(parts) => {
+                let mut last = start;
+                for part in parts {
+                    let (part_start, part_end) = self.build(part);
+                    self.states[last].epsilon.push(part_start);
+                    last = part_end;
+                }
+                self.states[last].epsilon.push(end);
+            }
+            Regex::Alternate(options) => {
+                for option in options {
+                    let (option_start, option_end) = self.build(option);
+                    self.states[start].epsilon.push(option_start);
+                    self.states[option_end].epsilon.push(end);
+                }
+            }
+            Regex::Star(inner) | Regex::Plus(inner) | Regex::Optional(inner) => {
+                let (inner_start, inner_end) = self.build(inner);
+                self.states[start].epsilon.push(inner_start);
+                self.states[inner_end].epsilon.push(end);
+                if !matches!(regex, Regex::Plus(_)) {
+                    self.states[start].epsilon.push(end);
+                }
+                if !matches!(regex, Regex::Optional(_)) {
+                    self.states[inner_end].epsilon.push(inner_start);
+                }
+            }
+        }
+        (start, end)
+    }
+
+    /// Adds every state reachable through epsilon moves
+    pub fn epsilon_closure(&self, set: &mut BTreeSet<usize>) {
+        let mut pending: Vec<usize> = set.iter().copied().collect();
+        while let Some(state) = pending.pop() {
+            for next in &self.states[state].epsilon {
+                if set.insert(*next) {
+                    pending.push(*next);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::Nfa;
+    use crate::lexer::regex::Regex;
+
+    /// Definitions accepted after reading all of `text`
+    fn accepted(nfa: &Nfa, text: &str) -> BTreeSet<usize> {
+        let mut current = BTreeSet::from([nfa.start]);
+        nfa.epsilon_closure(&mut current);
+        for symbol in text.chars().map(|symbol| symbol as u32) {
+            let mut next: BTreeSet<usize> = current.iter()
+                .flat_map(|state| nfa.states[*state].transitions.iter())
+                .filter(|(low, high, _)| *low <= symbol && symbol <= *high)
+                .map(|(_, _, target)| *target)
+                .collect();
+            nfa.epsilon_closure(&mut next);
+            current = next;
+        }
+        current.iter().filter_map(|state| nfa.states[*state].accept).collect()
+    }
+
+    #[test]
+    fn repetitions() {
+        let patterns = ["ab*", "ab+", "ab?c", "(a|b)(c|)"].map(|pattern| Regex::parse(pattern).unwrap());
+        let nfa = Nfa::new(&patterns);
+        for (text, definitions) in [("a", &[0, 3][..]), ("ab", &[0, 1]), ("abbb", &[0, 1]), ("ac", &[2, 3]), ("abc", &[2]), ("bc", &[3]), ("abbc", &[]), ("", &[])] {
+            assert_eq!(accepted(&nfa, text), definitions.iter().copied().collect(), "{:?}", text);
+        }
+    }
+}
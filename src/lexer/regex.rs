@@ -0,0 +1,264 @@
+/// Every code point, the range a negated class is taken from
+const ALL: (u32, u32) = (0, char::MAX as u32);
+
+/// Regular expression over ranges of code points
+#[derive(Clone, Debug)]
+pub enum Regex {
+    /// Matches the empty string
+    Empty,
+    /// One character inside any of the inclusive ranges
+    Class(Vec<(u32, u32)>),
+    Concat(Vec<Regex>),
+    Alternate(Vec<Regex>),
+    Star(Box<Regex>),
+    Plus(Box<Regex>),
+    Optional(Box<Regex>),
+}
+
+/// Position in the pattern and what went wrong there
+pub type RegexError = (usize, &'static str);
+
+impl Regex {
+    pub fn literal(symbol: char) -> Self {
+        Self::Class(vec![(symbol as u32, symbol as u32)])
+    }
+
+    /// Reads `|`, `*`, `+`, `?`, parentheses, `.`, classes like `[a-z_]` or `[^"]`
+    /// and the escapes `\d`, `\w`, `\s`, `\n`, `\t`, `\r`
+    pub fn parse(pattern: &str) -> Result<Self, RegexError> {
+        let mut parser = Parser { pattern: pattern.chars().collect(), position: 0 };
+        let regex = parser.alternate()?;
+        match parser.peek() {
+            None => Ok(regex),
+            Some(_) => Err((parser.position, "unmatched )")),
+        }
+    }
+}
+
+/// Sorted ranges with the overlapping and touching ones joined
+fn normalize(mut ranges: Vec<(u32, u32)>) -> Vec<(u32, u32)> {
+    ranges.sort();
+    let mut joined: Vec<(u32, u32)> = vec![];
+    for (low, high) in ranges {
+        match joined.last_mut() {
+            Some(last) if low <= last.1.saturating_add(1) => last.1 = last.1.max(high),
+            _ => joined.push((low, high)),
+        }
+    }
+    joined
+}
+
+fn complement(ranges: Vec<(u32, u32)>) -> Vec<(u32, u32)> {
+    let mut result = vec![];
+    let mut next = ALL.0;
+    for (low, high) in normalize(ranges) {
+        if low > next {
+            result.push((next, low - 1));
+        }
+        next = high + 1;
+    }
+    if next <= ALL.1 {
+        result.push((next, ALL.1));
+    }
+    result
+}
+
+struct Parser {
+    pattern: Vec<char>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.pattern.get(self.position).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let symbol = self.peek()?;
+        self.position += 1;
+        Some(symbol)
+    }
+
+    fn alternate(&mut self) -> Result<Regex, RegexError> {
+        let mut options = vec![self.concat()?];
+        while self.peek() == Some('|') {
+            self.position += 1;
+            options.push(self.concat()?);
+        }
+        Ok(match options.len() {
+            1 => options.pop().unwrap(),
+            _ => Regex::Alternate(options),
+        })
+    }
+
+    fn concat(&mut self) -> Result<Regex, RegexError> {
+        let mut parts = vec![];
+        while let Some(symbol) = self.peek() {
+            if symbol == '|' || symbol == ')' {
+                break;
+            }
+            parts.push(self.repeat()?);
+        }
+        Ok(match parts.len() {
+            0 => Regex::Empty,
+            1 => parts.pop().unwrap(),
+            _ => Regex::Concat(parts),
+        })
+    }
+
+    fn repeat(&mut self) -> Result<Regex, RegexError> {
+        let mut regex = self.atom()?;
+        loop {
+            regex = match self.peek() {
+                Some('*') => Regex::Star(Box::new(regex)),
+                Some('+') => Regex::Plus(Box::new(regex)),
+                Some('?') => Regex::Optional(Box::new(regex)),
+                _ => return Ok(regex),
+            };
+            self.position += 1;
+        }
+    }
+
+    fn atom(&mut self) -> Result<Regex, RegexError> {
+        let start = self.position;
+        match self.next() {
+            Some('(') => {
+                let regex = self.alternate()?;
+                match self.next() {
+                    Some(')') => Ok(regex),
+                    _ => Err((start, "unclosed (")),
+                }
+            }
+            Some('[') => self.class(start),
+            Some('.') => Ok(Regex::Class(complement(vec![('\n' as u32, '\n' as u32)]))),
+            Some('\\') => Ok(Regex::Class(self.escape()?)),
+            Some('*' | '+' | '?') => Err((start, "nothing to repeat")),
+            Some(symbol) => Ok(Regex::literal(symbol)),
+            None => Err((start, "pattern ends early")),
+        }
+    }
+
+    /// Ranges of the escape after a backslash
+    fn escape(&mut self) -> Result<Vec<(u32, u32)>, RegexError> {
+        let single = |symbol: char| vec![(symbol as u32, symbol as u32)];
+        match self.next() {
+            Some('d') => Ok(vec![('0' as u32, '9' as u32)]),
+            Some('w') => Ok(normalize(vec![('0' as u32, '9' as u32), ('A' as u32, 'Z' as u32), ('_' as u32, '_' as u32), ('a' as u32, 'z' as u32)])),
+            Some('s') => Ok(normalize(vec![(' ' as u32, ' ' as u32), ('\t' as u32, '\n' as u32), ('\r' as u32, '\r' as u32)])),
+            Some('n') => Ok(single('\n')),
+            Some('t') => Ok(single('\t')),
+            Some('r') => Ok(single('\r')),
+            Some(symbol) => Ok(single(symbol)),
+            None => Err((self.position - 1, "pattern ends after \\")),
+        }
+    }
+
+    /// A class after its `[`, a `]` right after the `[` or `[^` is taken literally
+    fn class(&mut self, start: usize) -> Result<Regex, RegexError> {
+        let negated = self.peek() == Some('^');
+        if negated {
+            self.position += 1;
+        }
+        let mut ranges = vec![];
+        let mut first = true;
+        loop {
+            let low = match self.next() {
+                Some(']') if !first => break,
+                Some('\\') => {
+                    let escaped = self.escape()?;
+                    match escaped[..] {
+                        [(low, high)] if low == high => low,
+                        _ => {
+                            ranges.extend(escaped);
+                            first = false;
+                            continue;
+                        }
+                    }
+                }
+                Some(symbol) => symbol as u32,
+                None => return Err((start, "unclosed [")),
+            };
+            first = false;
+            let high = match (self.peek(), self.pattern.get(self.position + 1)) {
+                (Some('-'), Some(high)) if *high != ']' => {
+                    self.position += 2;
+                    match *high {
+                        '\\' => match self.escape()?[..] {
+                            [(high, other)] if high == other => high,
+                            _ => return Err((self.position - 2, "range ends in a class")),
+                        },
+                        high => high as u32,
+                    }
+                }
+                _ => low,
+            };
+            if high < low {
+                return Err((self.position - 1, "range goes backwards"));
+            }
+            ranges.push((low, high));
+        }
+        Ok(Regex::Class(match negated {
+            true => complement(ranges),
+            false => normalize(ranges),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Regex, ALL};
+
+    fn class(pattern: &str) -> Vec<(u32, u32)> {
+        match Regex::parse(pattern).unwrap() {
+            Regex::Class(ranges) => ranges,
+            other => panic!("{:?} is not a class", other),
+        }
+    }
+
+    fn ranges(ranges: &[(char, char)]) -> Vec<(u32, u32)> {
+        ranges.iter().map(|(low, high)| (*low as u32, *high as u32)).collect()
+    }
+
+    #[test]
+    fn classes_and_ranges() {
+        assert_eq!(class("[a-c_0-9]"), ranges(&[('0', '9'), ('_', '_'), ('a', 'c')]));
+        // overlapping and touching ranges are joined
+        assert_eq!(class("[a-fc-hi]"), ranges(&[('a', 'i')]));
+        assert_eq!(class("[]a]"), ranges(&[(']', ']'), ('a', 'a')]));
+        assert_eq!(class("[a-]"), ranges(&[('-', '-'), ('a', 'a')]));
+        assert_eq!(class("x"), ranges(&[('x', 'x')]));
+    }
+
+    #[test]
+    fn negated_classes() {
+        assert_eq!(class("[^\"]"), vec![(ALL.0, '"' as u32 - 1), ('"' as u32 + 1, ALL.1)]);
+        assert_eq!(class("[^]]"), vec![(ALL.0, ']' as u32 - 1), (']' as u32 + 1, ALL.1)]);
+        assert_eq!(class("."), vec![(ALL.0, '\n' as u32 - 1), ('\n' as u32 + 1, ALL.1)]);
+    }
+
+    #[test]
+    fn escapes() {
+        assert_eq!(class("\\d"), ranges(&[('0', '9')]));
+        assert_eq!(class("\\w"), ranges(&[('0', '9'), ('A', 'Z'), ('_', '_'), ('a', 'z')]));
+        assert_eq!(class("\\s"), ranges(&[('\t', '\n'), ('\r', '\r'), (' ', ' ')]));
+        assert_eq!(class("\\n"), ranges(&[('\n', '\n')]));
+        assert_eq!(class("\\*"), ranges(&[('*', '*')]));
+        assert_eq!(class("[\\d_]"), ranges(&[('0', '9'), ('_', '_')]));
+        assert_eq!(class("[\\t-\\r]"), ranges(&[('\t', '\r')]));
+    }
+
+    #[test]
+    fn error_positions() {
+        for (pattern, error) in [
+            ("(ab", (0, "unclosed (")),
+            ("ab)", (2, "unmatched )")),
+            ("a|*", (2, "nothing to repeat")),
+            ("x[a-", (1, "unclosed [")),
+            ("ab\\", (2, "pattern ends after \\")),
+            ("[z-a]", (3, "range goes backwards")),
+            ("[a-\\d]", (3, "range ends in a class")),
+        ] {
+            assert_eq!(Regex::parse(pattern).unwrap_err(), error, "{}", pattern);
+        }
+    }
+}
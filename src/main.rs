@@ -4,10 +4,9 @@ extern crate core;
 
 use std::{env, fs};
 
-use prettytable::{Cell, Row, Table};
-
 use crate::parsing::ParsingProcess;
 use crate::parsing_table::IStateMachine;
+use crate::syntax::TerminalString;
 
 pub mod itemset;
 pub mod syntax;
@@ -17,6 +16,16 @@ pub mod rule_depend;
 mod tarjan;
 mod data_structure;
 pub mod first_follow;
+pub mod cnf;
+pub mod forest;
+pub mod earley;
+mod glr;
+pub mod ambiguity;
+pub mod generate;
+mod differential;
+pub mod diagnostic;
+pub mod lexer;
+mod viewer;
 
 fn main() {
     let file_path = "rule.txt";
@@ -28,122 +37,803 @@ fn main() {
     match runtype {
         Some("lr_zero") => lr_zero(file_path),
         Some("lalr_one")=> lalr_one(file_path),
+        Some("minimal_lr_one") => minimal_lr_one(file_path),
+        Some("lr_k") => lr_k(file_path, args.get(2).and_then(|k| k.parse().ok()).unwrap_or(2)),
+        Some("compressed") => compressed(file_path),
+        Some("states") => state_counts(file_path, args.get(2).and_then(|k| k.parse().ok()).unwrap_or(2)),
+        Some("cyk") => cyk(file_path),
+        Some("earley") => earley(file_path),
+        Some("glr") => glr(file_path),
+        Some("generate") => generate_sentences(
+            file_path,
+            args.get(2).and_then(|count| count.parse().ok()).unwrap_or(10),
+            args.get(3).and_then(|depth| depth.parse().ok()).unwrap_or(8),
+            args.get(4).map_or("sentences.txt", |path| path.as_str()),
+        ),
+        Some("coverage") => coverage_sentences(file_path, args.get(2).map_or("sentences.txt", |path| path.as_str())),
+        Some("corpus") => corpus(
+            file_path,
+            args.get(2).and_then(|count| count.parse().ok()).unwrap_or(20),
+            args.get(3).map_or("corpus.txt", |path| path.as_str()),
+        ),
+        Some("batch") => batch(
+            file_path,
+            args.get(2).map_or("sentences.txt", |path| path.as_str()),
+            args.get(3).map(|x| x.as_str()),
+            args.get(4).and_then(|k| k.parse().ok()).unwrap_or(2),
+        ),
+        Some("differential") => differential(file_path, args.get(2).and_then(|count| count.parse().ok()).unwrap_or(50)),
+        Some("lalr_check") => lalr_check(file_path),
+        Some("equivalence") => equivalence(file_path),
+        Some("lex") => lex(file_path),
+        Some("tree") => tree(file_path),
+        Some("stream") => stream(file_path),
+        Some("push") => push(file_path),
+        Some("reparse") => reparse(file_path),
+        Some("trace") => trace(file_path, args.get(2).map_or("md", |target| target.as_str())),
+        Some("edit") => edit(file_path),
+        Some("benchmark") => benchmark(
+            args.get(2).and_then(|statements| statements.parse().ok()).unwrap_or(20),
+            args.get(3).and_then(|words| words.parse().ok()).unwrap_or(8),
+            args.get(4).and_then(|threads| threads.parse().ok()),
+        ),
+        Some("ambiguity") => ambiguity(file_path, args.get(2).and_then(|length| length.parse().ok()).unwrap_or(6)),
 
         _ => lr_one(file_path),
     }
 }
 
-fn lr_zero(file_path: &str) {
-    use crate::itemset::LRZeroItemSets;
-    use parsing_table::lr_zero::StateMachine;
-    let mut itemsets = LRZeroItemSets::new('E');
+fn lr_zero_itemsets(file_path: &str) -> itemset::LRZeroItemSets {
+    let mut itemsets = itemset::LRZeroItemSets::new('E');
 
     for line in fs::read_to_string(file_path).unwrap().lines() {
         itemsets.add_from_string(line);
     }
 
     itemsets.generate_next();
-    let machine = StateMachine::from_itemset(&itemsets);
-    run_machine(&itemsets, machine);
+    itemsets
 }
 
-fn lalr_one(file_path: &str) {
-    use crate::itemset::LROneItemSets;
-    use parsing_table::lr_one::StateMachine;
-    let mut itemsets = LROneItemSets::new('E');
+fn lalr_one_itemsets(file_path: &str) -> itemset::LROneItemSets {
+    let mut itemsets = itemset::LROneItemSets::new('E');
 
     for line in fs::read_to_string(file_path).unwrap().lines() {
         itemsets.add_from_string(line);
     }
 
     itemsets.generate_lalr();
-    let machine = StateMachine::from_itemset(&itemsets);
-    run_machine(&itemsets, machine);
+    itemsets
 }
 
+fn minimal_lr_one_itemsets(file_path: &str) -> itemset::LROneItemSets {
+    let mut itemsets = itemset::LROneItemSets::new('E');
 
-fn lr_one(file_path: &str) {
-    use crate::itemset::LROneItemSets;
-    use parsing_table::lr_one::StateMachine;
-    let mut itemsets = LROneItemSets::new('E');
+    for line in fs::read_to_string(file_path).unwrap().lines() {
+        itemsets.add_from_string(line);
+    }
+
+    itemsets.generate_minimal();
+    itemsets
+}
+
+fn lr_one_itemsets(file_path: &str) -> itemset::LROneItemSets {
+    let mut itemsets = itemset::LROneItemSets::new('E');
 
     for line in fs::read_to_string(file_path).unwrap().lines() {
         itemsets.add_from_string(line);
     }
 
     itemsets.generate_next();
+    itemsets
+}
+
+fn lr_k_itemsets(file_path: &str, k: usize) -> itemset::LRkItemSets {
+    let mut itemsets = itemset::LRkItemSets::new('E', k);
+
+    for line in fs::read_to_string(file_path).unwrap().lines() {
+        itemsets.add_from_string(line);
+    }
+
+    itemsets.generate_next();
+    itemsets
+}
+
+fn lr_zero(file_path: &str) {
+    use parsing_table::lr_zero::StateMachine;
+    let itemsets = lr_zero_itemsets(file_path);
+    let machine = StateMachine::from_itemset(&itemsets);
+    if use_earley(&machine) {
+        return earley(file_path);
+    }
+    run_machine(file_path, &itemsets, machine);
+}
+
+fn lalr_one(file_path: &str) {
+    use parsing_table::lr_one::StateMachine;
+    let itemsets = lalr_one_itemsets(file_path);
+    let machine = StateMachine::from_itemset(&itemsets);
+    if use_earley(&machine) {
+        return earley(file_path);
+    }
+    run_machine(file_path, &itemsets, machine);
+}
+
+fn minimal_lr_one(file_path: &str) {
+    use parsing_table::lr_one::StateMachine;
+    let itemsets = minimal_lr_one_itemsets(file_path);
+    let machine = StateMachine::from_itemset(&itemsets);
+    if use_earley(&machine) {
+        return earley(file_path);
+    }
+    run_machine(file_path, &itemsets, machine);
+}
+
+fn compressed(file_path: &str) {
+    use parsing_table::compressed::StateMachine;
+    let itemsets = lr_one_itemsets(file_path);
+    let machine = StateMachine::from_itemset(&itemsets);
+    println!("table entries take {} bytes, packed {} bytes ({:.0}%)",
+             machine.unpacked_memory, machine.memory(), 100.0 * machine.memory() as f64 / machine.unpacked_memory.max(1) as f64);
+    if use_earley(&machine) {
+        return earley(file_path);
+    }
+    run_machine(file_path, &itemsets, machine);
+}
+
+fn lr_k(file_path: &str, k: usize) {
+    use parsing_table::lr_k::StateMachine;
+    let itemsets = lr_k_itemsets(file_path, k);
+    let machine = StateMachine::from_itemset(&itemsets);
+    if use_earley(&machine) {
+        return earley(file_path);
+    }
+    run_machine(file_path, &itemsets, machine);
+}
+
+/// Number of states and conflicts of every construction, `k` for the lr(k) row
+fn state_counts(file_path: &str, k: usize) {
+    use parsing_table::{lr_k, lr_one, lr_zero};
+    let lr_zero = lr_zero_itemsets(file_path);
+    let mut table = prettytable::Table::new();
+    table.set_titles(prettytable::row!["Parser", "States", "Conflicts"]);
+    table.add_row(prettytable::row!["lr(0)", lr_zero.sets.len(), lr_zero::StateMachine::from_itemset(&lr_zero).conflicts().len()]);
+    for (name, itemsets) in [
+        ("lalr(1)", lalr_one_itemsets(file_path)),
+        ("minimal lr(1)", minimal_lr_one_itemsets(file_path)),
+        ("lr(1)", lr_one_itemsets(file_path)),
+    ] {
+        table.add_row(prettytable::row![name, itemsets.sets.len(), lr_one::StateMachine::from_itemset(&itemsets).conflicts().len()]);
+    }
+    let lr_k = lr_k_itemsets(file_path, k);
+    table.add_row(prettytable::row![format!("lr({})", k), lr_k.sets.len(), lr_k::StateMachine::from_itemset(&lr_k).conflicts().len()]);
+    table.printstd();
+}
+
+fn lr_one(file_path: &str) {
+    use parsing_table::lr_one::StateMachine;
+    let itemsets = lr_one_itemsets(file_path);
     // println!("{}", &itemsets);
     let machine = StateMachine::from_itemset(&itemsets);
-    run_machine(&itemsets, machine);
+    if use_earley(&machine) {
+        return earley(file_path);
+    }
+    run_machine(file_path, &itemsets, machine);
 }
 
-fn run_machine<ItemSets, StateMachine>(itemset: &ItemSets, machine: StateMachine)
-    where StateMachine: for<'a> IStateMachine<'a, ItemSets = ItemSets>{
-    println!("{:20}", machine.display(&itemset));
+fn read_rules(file_path: &str) -> Vec<syntax::Rule> {
+    fs::read_to_string(file_path).unwrap().lines().filter_map(|line| line.try_into().ok()).collect()
+}
+
+fn cyk(file_path: &str) {
+    use crate::cnf::{ChomskyNormalForm, CykTable};
+    let grammar = ChomskyNormalForm::from_rules(&read_rules(file_path), syntax::Variable { symbol: 'E' });
+    println!("Chomsky normal form, start {}", grammar.start);
+    for rule in &grammar.rules {
+        println!("    {}", rule);
+    }
     print!("\nTaking input\n");
-    let mut line = std::io::stdin().lines().next().unwrap().unwrap();
-    line.push(syntax::END_TERMINAL);
+    let line = std::io::stdin().lines().next().unwrap().unwrap();
     let input_vec: Vec<char> = line.chars().collect();
-    let parser = ParsingProcess::new(&input_vec);
-    print!("{esc}[2J{esc}[1;1H", esc = 27 as char);
-
-    run_parsing(&machine, vec![parser]);
+    let table = CykTable::new(&grammar, &input_vec.as_slice().into());
 
+    let g = getch_rs::Getch::new();
+    let mut length = 1;
+    loop {
+        clear_screen();
+        table.table(length).printstd();
+        if length >= table.len() {
+            println!("{}", if table.accepts() { "Accept" } else { "Reject" });
+        }
+        println!("Press right arrow to fill the next span length, left arrow to go back 1 step, down arrow to exit, up arrow to reset");
+        let Ok(key_press) = g.getch() else {break};
+        match key_press {
+            getch_rs::Key::Right => length = (length + 1).min(table.len().max(1)),
+            getch_rs::Key::Left => length = length.saturating_sub(1).max(1),
+            getch_rs::Key::Down => return,
+            getch_rs::Key::Up => length = 1,
+            _ => continue
+        }
+    }
 }
 
-fn clear_screen() {
-    println!("{esc}[2J{esc}[1;1H", esc = 27 as char);
+/// `--earley-fallback` parses with Earley whenever the LR table has conflicts
+fn use_earley<StateMachine>(machine: &StateMachine) -> bool
+    where StateMachine: for<'a> IStateMachine<'a> {
+    if machine.conflicts().is_empty() || !env::args().any(|arg| arg == "--earley-fallback") {
+        return false;
+    }
+    println!("LR table has {} conflicts, falling back to earley", machine.conflicts().len());
+    true
 }
 
-fn run_parsing<IStateMachine>(machine: &IStateMachine, mut history: Vec<ParsingProcess>)
-where IStateMachine: for<'a> parsing_table::IStateMachine<'a>
-{
+fn earley(file_path: &str) {
+    let parser = earley::Earley::new(&read_rules(file_path), syntax::Variable { symbol: 'E' });
+    print!("\nTaking input\n");
+    let line = std::io::stdin().lines().next().unwrap().unwrap();
+    let input_vec: Vec<char> = line.chars().collect();
+    let chart = parser.parse(&input_vec.as_slice().into());
+
     let g = getch_rs::Getch::new();
+    let mut last_set = 0;
     loop {
         clear_screen();
-        let mut table = Table::new();
-        table.add_row(Row::new(vec![
-            Cell::new("Step"),
-            Cell::new("State"),
-            Cell::new("Stack"),
-            Cell::new("Output"),
-            Cell::new("Input"),
-            Cell::new("Action"),
+        chart.table(last_set).printstd();
+        if last_set == chart.input.len() {
+            match chart.forest() {
+                Some(forest) => print!("Accept\n{}", forest),
+                None => println!("Reject"),
+            }
+        }
+        println!("Press right arrow to view next set, left arrow to go back 1 set, down arrow to exit, up arrow to reset");
+        let Ok(key_press) = g.getch() else {break};
+        match key_press {
+            getch_rs::Key::Right => last_set = (last_set + 1).min(chart.input.len()),
+            getch_rs::Key::Left => last_set = last_set.saturating_sub(1),
+            getch_rs::Key::Down => return,
+            getch_rs::Key::Up => last_set = 0,
+            _ => continue
+        }
+    }
+}
+
+fn glr(file_path: &str) {
+    use parsing_table::lr_one::StateMachine;
+    let itemsets = lr_one_itemsets(file_path);
+    let machine = StateMachine::from_itemset(&itemsets);
+    println!("{:20}", machine.display(&itemsets));
+    print!("\nTaking input\n");
+    let mut line = std::io::stdin().lines().next().unwrap().unwrap();
+    line.push(syntax::END_TERMINAL);
+    let input_vec: Vec<char> = line.chars().collect();
+    match glr::Glr::new(&machine, &itemsets.rules).parse(&input_vec.as_slice().into()) {
+        Some(forest) if forest.is_ambiguous() => print!("Accept, ambiguous\n{}", forest),
+        Some(forest) => print!("Accept\n{}", forest),
+        None => println!("Reject"),
+    }
+}
+
+fn ambiguity(file_path: &str, max_length: usize) {
+    use parsing_table::lr_one::StateMachine;
+    let itemsets = lr_one_itemsets(file_path);
+    let conflicts = StateMachine::from_itemset(&itemsets).conflicts().len();
+    println!("lr(1) table has {} conflicts", conflicts);
 
-        ]));
-        for (step, parser) in history.iter().enumerate() {
-            table.add_row(parser.display(machine).get_row(step));
+    let ambiguities = ambiguity::find_ambiguities(&read_rules(file_path), syntax::Variable { symbol: 'E' }, max_length);
+    if ambiguities.is_empty() {
+        println!("no ambiguous sentence up to length {}", max_length);
+        if conflicts != 0 {
+            println!("the conflicts may only need more lookahead");
         }
+    }
+    for found in ambiguities {
+        println!("ambiguous sentence {:?}", TerminalString { data: found.sentence });
+        println!("    {}", found.trees.0);
+        println!("    {}", found.trees.1);
+    }
+}
 
-        table.printstd();
+fn generate_sentences(file_path: &str, count: usize, max_depth: usize, output_path: &str) {
+    let rules = read_rules(file_path);
+    let generator = generate::SentenceGenerator::new(&rules, syntax::Variable { symbol: 'E' });
+    let mut random = generate::Random::from_time();
+    let sentences: Vec<TerminalString> = (0..count).filter_map(|_| generator.random(&mut random, max_depth)).collect();
+    generate::write_sentences(output_path, &sentences).unwrap();
+    println!("wrote {} sentences to {}", sentences.len(), output_path);
+}
 
-        println!("Press right arrow to view next step, left arrow to go back 1 step, down arrow to exit, up arrow to reset");
-        let Ok(key_press) = g.getch() else {break};
-        match key_press {
-            getch_rs::Key::Right => {
-                let Some(next_step) = history.last().unwrap().get_next(machine) else {
-                    break;
-                };
-                history.push(next_step);
+fn coverage_sentences(file_path: &str, output_path: &str) {
+    let rules = read_rules(file_path);
+    let generator = generate::SentenceGenerator::new(&rules, syntax::Variable { symbol: 'E' });
+    let (sentences, uncovered) = generator.coverage();
+    for rule in uncovered {
+        println!("rule {} can not be used by any sentence", rules[rule]);
+    }
+    generate::write_sentences(output_path, &sentences).unwrap();
+    println!("wrote {} sentences to {}", sentences.len(), output_path);
+}
+
+/// Valid sentences together with mutants of them the earley recognizer rejects
+fn corpus(file_path: &str, count: usize, output_path: &str) {
+    let rules = read_rules(file_path);
+    let samples = generate::corpus(&rules, syntax::Variable { symbol: 'E' }, count, &mut generate::Random::from_time());
+    generate::write_corpus(output_path, &samples).unwrap();
+    let rejected = samples.iter().filter(|sample| sample.expected == Some(false)).count();
+    println!("wrote {} accepted and {} rejected sentences to {}", samples.len() - rejected, rejected, output_path);
+}
+
+/// A parse still running after `differential::MAX_STEPS` steps counts as rejected
+fn accepts<StateMachine>(machine: &StateMachine, sentence: &TerminalString) -> bool
+    where StateMachine: for<'a> IStateMachine<'a> {
+    let mut input_vec: Vec<char> = sentence.data.iter().map(|terminal| terminal.symbol).collect();
+    input_vec.push(syntax::END_TERMINAL);
+    let mut parser = ParsingProcess::new(&input_vec);
+    for _ in 0..differential::MAX_STEPS {
+        if let Some(result) = parser.run(machine) {
+            return result;
+        }
+    }
+    false
+}
+
+fn batch(file_path: &str, input_path: &str, machine_type: Option<&str>, k: usize) {
+    match machine_type {
+        Some("compressed") => batch_machine(&parsing_table::compressed::StateMachine::from_itemset(&lr_one_itemsets(file_path)), input_path),
+        Some("minimized") => batch_machine(&parsing_table::equivalence::Behavior::from_itemset(&lr_one_itemsets(file_path)), input_path),
+        Some("lr_k") => batch_machine(&parsing_table::lr_k::StateMachine::from_itemset(&lr_k_itemsets(file_path, k)), input_path),
+        Some("lr_zero") => batch_machine(&parsing_table::lr_zero::StateMachine::from_itemset(&lr_zero_itemsets(file_path)), input_path),
+        Some("lalr_one") => batch_machine(&parsing_table::lr_one::StateMachine::from_itemset(&lalr_one_itemsets(file_path)), input_path),
+        Some("minimal_lr_one") => batch_machine(&parsing_table::lr_one::StateMachine::from_itemset(&minimal_lr_one_itemsets(file_path)), input_path),
+        _ => batch_machine(&parsing_table::lr_one::StateMachine::from_itemset(&lr_one_itemsets(file_path)), input_path),
+    }
+}
+
+fn batch_machine<StateMachine>(machine: &StateMachine, input_path: &str)
+    where StateMachine: for<'a> IStateMachine<'a> {
+    let samples = generate::read_corpus(input_path).unwrap();
+    let mut accepted = 0;
+    let mut mismatches = 0;
+    for sample in &samples {
+        let result = accepts(machine, &sample.sentence);
+        accepted += result as usize;
+        let verdict = if result { "accept" } else { "reject" };
+        match sample.expected {
+            Some(expected) if expected != result => {
+                mismatches += 1;
+                println!("{:6} {} MISMATCH", verdict, sample.sentence);
+            }
+            _ => println!("{:6} {}", verdict, sample.sentence),
+        }
+    }
+    println!("{} of {} sentences accepted, {} mismatches", accepted, samples.len(), mismatches);
+}
+
+/// Runs generated sentences and their mutants through every conflict free machine
+fn differential(file_path: &str, count: usize) {
+    use differential::Candidate;
+    let rules = read_rules(file_path);
+    let generator = generate::SentenceGenerator::new(&rules, syntax::Variable { symbol: 'E' });
+    let alphabet = generate::alphabet(&rules);
+    let mut random = generate::Random::from_time();
+    let mut inputs = generator.coverage().0;
+    inputs.extend((0..count).filter_map(|_| generator.random(&mut random, 8)));
+    let mutants: Vec<TerminalString> = inputs.iter().map(|input| generate::mutate(input, &alphabet, &mut random)).collect();
+    inputs.extend(mutants);
+
+    let lr_zero = parsing_table::lr_zero::StateMachine::from_itemset(&lr_zero_itemsets(file_path));
+    let lalr_one = parsing_table::lr_one::StateMachine::from_itemset(&lalr_one_itemsets(file_path));
+    let minimal_lr_one = parsing_table::lr_one::StateMachine::from_itemset(&minimal_lr_one_itemsets(file_path));
+    let lr_one = parsing_table::lr_one::StateMachine::from_itemset(&lr_one_itemsets(file_path));
+    let mut candidates = vec![];
+    for (candidate, conflicts) in [
+        (Candidate::new("lr(0)", &lr_zero), lr_zero.conflicts().len()),
+        (Candidate::new("lalr(1)", &lalr_one), lalr_one.conflicts().len()),
+        (Candidate::new("minimal lr(1)", &minimal_lr_one), minimal_lr_one.conflicts().len()),
+        (Candidate::new("lr(1)", &lr_one), lr_one.conflicts().len()),
+    ] {
+        match conflicts {
+            0 => candidates.push(candidate),
+            _ => println!("skipping {}, its table has {} conflicts", candidate.name, conflicts),
+        }
+    }
+    if candidates.len() < 2 {
+        println!("less than two conflict free machines, nothing to compare");
+        return;
+    }
+
+    let disagreements = differential::compare(&candidates, &inputs);
+    let Some(shortest) = disagreements.first() else {
+        println!("all machines agree on {} inputs", inputs.len());
+        return;
+    };
+    println!("{} of {} inputs disagree, shortest input {:?}", disagreements.len(), inputs.len(), shortest.input);
+    for (name, trace) in [&shortest.left, &shortest.right] {
+        println!("{} {}", name, if trace.accepted { "accepts" } else { "rejects" });
+        trace.table.printstd();
+    }
+}
+
+/// Compares the lalr(1) lookaheads with the canonical lr(1) states merged by core
+fn lalr_check(file_path: &str) {
+    let lalr = lalr_one_itemsets(file_path);
+    let canonical = lr_one_itemsets(file_path);
+    println!("lalr(1) has {} states, lr(1) has {} states", lalr.sets.len(), canonical.sets.len());
+    let (lalr, canonical) = (lalr.merged_lookaheads(), canonical.merged_lookaheads());
+    let mut mismatches = 0;
+    for (core, lookaheads) in &lalr {
+        if canonical.get(core) != Some(lookaheads) {
+            mismatches += 1;
+            println!("lookaheads differ on core {:?}", core);
+        }
+    }
+    mismatches += canonical.keys().filter(|core| !lalr.contains_key(*core)).count();
+    match mismatches {
+        0 => println!("lalr(1) matches the merged lr(1) states"),
+        _ => println!("{} cores differ", mismatches),
+    }
+}
+
+/// Items (rule, dot, lookahead) of every state
+type Automaton = std::collections::BTreeSet<std::collections::BTreeSet<(usize, usize, char)>>;
+
+fn lr_one_automaton(itemsets: &itemset::LROneItemSets) -> Automaton {
+    use itemset::Item as _;
+    itemsets.sets.iter()
+        .map(|set| set.items.iter().map(|item| (item.rule_number, item.dot(), item.follow().symbol)).collect())
+        .collect()
+}
+
+/// Number of states left after merging states with the same behavior, and whether the builders
+/// give the same machine up to the numbering of the states
+fn equivalence(file_path: &str) {
+    use parsing_table::equivalence::Behavior;
+    use parsing_table::{lr_one, lr_zero};
+    let lr_zero_sets = lr_zero_itemsets(file_path);
+    let lr_zero = Behavior::of(&lr_zero::StateMachine::from_itemset(&lr_zero_sets), &lr_zero_sets.rules);
+    let lr_one = |itemsets: &itemset::LROneItemSets| Behavior::of(&lr_one::StateMachine::from_itemset(itemsets), &itemsets.rules);
+    let build = |generate: fn(&mut itemset::LROneItemSets)| {
+        let mut itemsets = itemset::LROneItemSets::new('E');
+        for line in fs::read_to_string(file_path).unwrap().lines() {
+            itemsets.add_from_string(line);
+        }
+        generate(&mut itemsets);
+        lr_one(&itemsets)
+    };
+    let machines = [
+        ("lr(0)", lr_zero),
+        ("lalr(1)", lr_one(&lalr_one_itemsets(file_path))),
+        ("minimal lr(1)", lr_one(&minimal_lr_one_itemsets(file_path))),
+        ("lr(1)", build(itemset::LROneItemSets::generate_next)),
+        ("lr(1) fast", build(itemset::LROneItemSets::generate_fast)),
+        ("lr(1) parallel", build(|itemsets| itemsets.generate_parallel(4))),
+    ];
+    let mut table = prettytable::Table::new();
+    table.set_titles(prettytable::row!["Parser", "States", "Minimized", "Same machine as"]);
+    for (index, (name, machine)) in machines.iter().enumerate() {
+        let minimized = machine.minimized();
+        let same: Vec<&str> = machines[..index].iter()
+            .filter(|(_, other)| other.minimized().isomorphism(&minimized).is_some())
+            .map(|(other, _)| *other)
+            .collect();
+        table.add_row(prettytable::row![name, machine.len(), minimized.len(), same.join(", ")]);
+    }
+    table.printstd();
+}
+
+/// Reads `+A:xB` to add a rule and `-3` to remove rule 3, the lr(1) automaton is updated after every edit
+fn edit(file_path: &str) {
+    let mut itemsets = lr_one_itemsets(file_path);
+    println!("{} states", itemsets.sets.len());
+    println!("Type +A:xB to add a rule, -3 to remove rule 3, an empty line to exit");
+    for line in std::io::stdin().lines() {
+        let line = line.unwrap();
+        let recomputed = match (line.strip_prefix('+'), line.strip_prefix('-')) {
+            (Some(rule), _) => match syntax::Rule::try_from(rule) {
+                Ok(rule) => itemsets.add_rule(rule),
+                Err(_) => {
+                    println!("can not read rule {}", rule);
+                    continue;
+                }
             },
-            getch_rs::Key::Left => {
-                if history.len() > 1 {
-                    history.pop();
+            (_, Some(number)) => match number.parse().ok().and_then(|number| itemsets.remove_rule(number)) {
+                Some(recomputed) => recomputed,
+                None => {
+                    println!("no rule {} to remove", number);
+                    continue;
                 }
             },
-            getch_rs::Key::Down=> {
-                return;
+            _ => return,
+        };
+        for (number, rule) in itemsets.rules.iter().enumerate() {
+            println!("{:3} {}", number, rule);
+        }
+        let mut rebuilt = itemset::LROneItemSets::new('E');
+        for rule in &itemsets.rules[1..] {
+            rebuilt.add_rule(rule.clone());
+        }
+        rebuilt.generate_next();
+        let matches = lr_one_automaton(&rebuilt) == lr_one_automaton(&itemsets);
+        println!("{} states, recomputed {:?}, {}", itemsets.sets.len(), recomputed, if matches { "same as a full rebuild" } else { "DIFFERENT from a full rebuild" });
+    }
+}
+
+/// Times `generate_next` against the fast and the parallel builders on a generated grammar
+/// and checks they all give the same item sets
+fn benchmark(statements: usize, words: usize, threads: Option<usize>) {
+    let rules = generate::benchmark_grammar(statements, words);
+    let threads = threads.unwrap_or_else(|| std::thread::available_parallelism().map_or(1, |threads| threads.get()));
+    println!("grammar with {} rules, {} threads", rules.len(), threads);
+    let mut baseline: Option<(itemset::LROneItemSets, std::time::Duration)> = None;
+    for builder in ["generate_next", "generate_fast", "generate_parallel"] {
+        let mut itemsets = itemset::LROneItemSets::new('E');
+        for rule in &rules {
+            itemsets.add_rule(rule.clone());
+        }
+        let start = std::time::Instant::now();
+        match builder {
+            "generate_fast" => itemsets.generate_fast(),
+            "generate_parallel" => itemsets.generate_parallel(threads),
+            _ => itemsets.generate_next(),
+        }
+        let elapsed = start.elapsed();
+        print!("{:18} {} states in {:?}", builder, itemsets.sets.len(), elapsed);
+        match &baseline {
+            None => {
+                println!();
+                baseline = Some((itemsets, elapsed));
             }
-            getch_rs::Key::Up=> {
-                while history.len() > 1 {
-                    history.pop();
-                }
+            Some((slow, slow_elapsed)) => {
+                let same = slow.sets == itemsets.sets && slow.ordering_map == itemsets.ordering_map;
+                println!(", {}, {:.1} times faster", if same { "same item sets" } else { "DIFFERENT item sets" }, slow_elapsed.as_secs_f64() / elapsed.as_secs_f64());
             }
+        }
+    }
+}
 
-            _ => continue
+/// Lexer of the `%` lines of the grammar file, `None` when every character is a terminal
+fn read_lexer(file_path: &str) -> Option<lexer::Lexer> {
+    let source = fs::read_to_string(file_path).unwrap();
+    match lexer::Lexer::from_source(&source, &read_rules(file_path))? {
+        Ok(lexer) => Some(lexer),
+        Err(error) => {
+            print!("{}", diagnostic::Diagnostic::definition(&error, &source, file_path).render(&source, diagnostic::Style::from_args()));
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Warnings pointing at the rules of the grammar file whose table entries were dropped
+fn report_conflicts<StateMachine>(file_path: &str, machine: &StateMachine)
+    where StateMachine: for<'a> IStateMachine<'a> {
+    let source = fs::read_to_string(file_path).unwrap();
+    for conflict in machine.conflicts() {
+        print!("{}", diagnostic::Diagnostic::conflict(conflict, &source, file_path).render(&source, diagnostic::Style::from_args()));
+    }
+}
+
+/// Token definitions and their automaton, then the tokens of every line of the input
+fn lex(file_path: &str) {
+    let Some(lexer) = read_lexer(file_path) else {
+        println!("{} has no token definitions, every character is a terminal", file_path);
+        return;
+    };
+    print!("{}", lexer);
+    for line in std::io::stdin().lines() {
+        match lexer.tokens(&line.unwrap()) {
+            Ok(tokens) => tokens.iter().for_each(|token| println!("{}", token)),
+            Err(error) => println!("{}", error),
+        }
+    }
+}
+
+/// Parser of one line of input, split by the lexer when the grammar file defines tokens
+fn read_input(lexer: Option<&lexer::Lexer>, mut line: String) -> Result<ParsingProcess, lexer::LexError> {
+    match lexer {
+        Some(lexer) => Ok(ParsingProcess::from_tokens(&lexer.tokens(&line)?)),
+        None => {
+            line.push(syntax::END_TERMINAL);
+            let input_vec: Vec<char> = line.chars().collect();
+            Ok(ParsingProcess::new(&input_vec))
+        }
+    }
+}
+
+/// Parse tree with the line and column span of every node for every line of the input,
+/// a syntax error is shown on its line with the terminals the parser expected
+fn tree(file_path: &str) {
+    use diagnostic::Diagnostic;
+    let machine = parsing_table::lr_one::StateMachine::from_itemset(&lr_one_itemsets(file_path));
+    report_conflicts(file_path, &machine);
+    let (lexer, rules, style) = (read_lexer(file_path), read_rules(file_path), diagnostic::Style::from_args());
+    let mut source = String::new();
+    let mut start = lexer::Location::default();
+    for line in std::io::stdin().lines() {
+        let line = line.unwrap();
+        source.push_str(&line);
+        source.push('\n');
+        let diagnostic = match read_input(lexer.as_ref(), line.clone()).map(|parser| parser.parse(&machine)) {
+            Ok(Ok(tree)) => {
+                print!("{}", tree);
+                None
+            }
+            Ok(Err(error)) => Some(Diagnostic::syntax(&error, &machine, &rules, "<stdin>")),
+            Err(error) => Some(Diagnostic::lex(&error, "<stdin>")),
+        };
+        if let Some(mut diagnostic) = diagnostic {
+            diagnostic.span = diagnostic.span.map(|span| span.within(start));
+            print!("{}", diagnostic.render(&source, style));
         }
+        start = line.chars().chain(['\n']).fold(start, |location, symbol| location.advance(symbol));
+    }
+}
+
+/// Parses all of the input as one text, tokens are lexed and parsed as the lines come in
+/// and reading stops at the first error
+fn stream(file_path: &str) {
+    use parsing::stream::StreamParser;
+    let machine = parsing_table::lr_one::StateMachine::from_itemset(&lr_one_itemsets(file_path));
+    let lines = std::io::stdin().lines().map_while(Result::ok);
+    let lexer = read_lexer(file_path);
+    let mut lex_error = None;
+    let result = match &lexer {
+        Some(lexer) => {
+            let tokens = lexer.lines(lines).map_while(|token| token.map_err(|error| lex_error = Some(error)).ok());
+            StreamParser::new(&machine, tokens).recognize()
+        }
+        None => StreamParser::new(&machine, lexer::characters(lines.flat_map(|line| line.chars().chain(['\n']).collect::<Vec<char>>()))).recognize(),
+    };
+    match (lex_error, result) {
+        (Some(error), _) => println!("{}", error),
+        (None, Ok(reductions)) => println!("accepted after {} reductions", reductions),
+        (None, Err(error)) => println!("{}", error),
+    }
+}
+
+/// Feeds the tokens of the input to a push parser one at a time and prints the reductions
+/// and the stack after each, a rejected token is skipped
+fn push(file_path: &str) {
+    use parsing::push::PushParser;
+    let machine = parsing_table::lr_one::StateMachine::from_itemset(&lr_one_itemsets(file_path));
+    let lines = std::io::stdin().lines().map_while(Result::ok);
+    let lexer = read_lexer(file_path);
+    let tokens: Box<dyn Iterator<Item = Result<lexer::Token, lexer::LexError>>> = match &lexer {
+        Some(lexer) => Box::new(lexer.lines(lines)),
+        None => Box::new(lexer::characters(lines.flat_map(|line| line.chars().chain(['\n']).collect::<Vec<char>>())).map(Ok)),
+    };
+    let mut parser = PushParser::new(&machine);
+    for token in tokens {
+        let token = match token {
+            Ok(token) => token,
+            Err(error) => return println!("{}", error),
+        };
+        print!("{:24}", token.to_string());
+        match parser.push(token) {
+            Ok(reductions) => {
+                let reduced: Vec<String> = reductions.iter().map(|reduction| format!("{} {}", reduction.variable, reduction.span)).collect();
+                println!("stack {:?}, reduced [{}]", parser.process().states(), reduced.join(", "));
+            }
+            Err(error) => println!("{}, skipped", error),
+        }
+    }
+    match parser.finish() {
+        Ok((_, tree)) => print!("{}", tree),
+        Err(error) => println!("{}", error),
+    }
+}
 
+/// Reads a line of input, then edits `3 5 text` replacing bytes 3..5 by `text`, the tree after every edit
+/// reuses what it can of the one before
+fn reparse(file_path: &str) {
+    use parsing::incremental::{self, Edit};
+    let machine = parsing_table::lr_one::StateMachine::from_itemset(&lr_one_itemsets(file_path));
+    let lexer = read_lexer(file_path);
+    let tokens = |text: &str| match &lexer {
+        Some(lexer) => lexer.tokens(text),
+        None => Ok(lexer::characters(text.chars()).collect()),
+    };
+    let mut lines = std::io::stdin().lines().map_while(Result::ok);
+    let Some(mut text) = lines.next() else {
+        return;
+    };
+    let mut tree = match tokens(&text).map(|tokens| ParsingProcess::from_tokens(&tokens).parse(&machine)) {
+        Ok(Ok(tree)) => tree,
+        Ok(Err(error)) => return println!("{}", error),
+        Err(error) => return println!("{}", error),
+    };
+    println!("{} nodes", tree.size());
+    println!("Type 3 5 text to replace bytes 3..5 by text, an empty line to exit");
+    for line in lines {
+        let mut parts = line.splitn(3, ' ');
+        let (Some(Ok(start)), Some(Ok(end))) = (parts.next().map(str::parse::<usize>), parts.next().map(str::parse::<usize>)) else {
+            return;
+        };
+        let inserted = parts.next().unwrap_or_default();
+        if start > end || !text.is_char_boundary(start) || !text.is_char_boundary(end) {
+            println!("{}..{} is not a range of {:?}", start, end, text);
+            continue;
+        }
+        let edited = format!("{}{}{}", &text[..start], inserted, &text[end..]);
+        let new_tokens = match tokens(&edited) {
+            Ok(new_tokens) => new_tokens,
+            Err(error) => {
+                println!("{}", error);
+                continue;
+            }
+        };
+        match incremental::reparse(&machine, &tree, Edit { start, end, inserted: inserted.len() }, &new_tokens) {
+            Ok(reparsed) => {
+                println!("{:?}: reused {} of {} nodes", edited, reparsed.reused, reparsed.tree.size());
+                text = edited;
+                tree = reparsed.tree;
+            }
+            Err(error) => println!("{:?}: {}, the edit is undone", edited, error),
+        }
+    }
+}
 
+/// Every step of the parse of the first line of the input as json, csv or markdown, written to the file
+/// named by `target` or printed when `target` is only the format
+fn trace(file_path: &str, target: &str) {
+    use parsing::trace;
+    let Some(format) = trace::Format::from_name(target) else {
+        println!("unknown trace format {}, use json, csv or md", target);
+        return;
+    };
+    let machine = parsing_table::lr_one::StateMachine::from_itemset(&lr_one_itemsets(file_path));
+    let line = match std::io::stdin().lines().next() {
+        Some(Ok(line)) => line,
+        Some(Err(error)) => return println!("can not read the input: {}", error),
+        None => return println!("no input to trace"),
+    };
+    let mut history = match read_input(read_lexer(file_path).as_ref(), line.clone()) {
+        Ok(parser) => vec![parser],
+        Err(error) => return print!("{}", diagnostic::Diagnostic::lex(&error, "<stdin>").render(&line, diagnostic::Style::from_args())),
+    };
+    while let Some(next) = history.last().unwrap().get_next(&machine) {
+        history.push(next);
+    }
+    let output = trace::export(&trace::steps(&machine, &history), format);
+    match target.contains('.') {
+        true => match fs::write(target, output) {
+            Ok(()) => println!("{} steps written to {}", history.len(), target),
+            Err(error) => println!("can not write {}: {}", target, error),
+        },
+        false => print!("{}", output),
     }
+}
+
+fn run_machine<ItemSets, StateMachine>(file_path: &str, itemset: &ItemSets, machine: StateMachine)
+    where StateMachine: for<'a> IStateMachine<'a, ItemSets = ItemSets>, ItemSets: for<'a> itemset::ItemSets<'a> {
+    println!("{:20}", machine.display(itemset));
+    report_conflicts(file_path, &machine);
+    print!("\nTaking input\n");
+    let line = std::io::stdin().lines().next().unwrap().unwrap();
+    let lexer = read_lexer(file_path);
+    let parser = match read_input(lexer.as_ref(), line.clone()) {
+        Ok(parser) => parser,
+        Err(error) => return print!("{}", diagnostic::Diagnostic::lex(&error, "<stdin>").render(&line, diagnostic::Style::from_args())),
+    };
+    print!("{esc}[2J{esc}[1;1H", esc = 27 as char);
+
+    let read = Box::new(move |line: &str| read_input(lexer.as_ref(), line.to_string()).map_err(|error| error.to_string()));
+    run_parsing(viewer::Viewer::new(&machine, viewer::state_items(itemset), line, parser, read));
+
+}
 
+fn clear_screen() {
+    println!("{esc}[2J{esc}[1;1H", esc = 27 as char);
+}
+
+fn run_parsing<IStateMachine>(mut viewer: viewer::Viewer<IStateMachine>)
+where IStateMachine: for<'a> parsing_table::IStateMachine<'a>
+{
+    let g = getch_rs::Getch::new();
+    let style = diagnostic::Style::from_args();
+    loop {
+        clear_screen();
+        print!("{}", viewer.render(style));
+        let Ok(key_press) = g.getch() else {break};
+        if !viewer.handle(key_press) {
+            return;
+        }
+    }
 }
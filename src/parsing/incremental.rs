@@ -0,0 +1,253 @@
+use crate::lexer::{Span, Token};
+use crate::parsing::{Node, SyntaxError};
+use crate::parsing_table::{Action, IStateMachine};
+use crate::syntax::{MixedChar, Terminal};
+
+/// Bytes `start..end` of the old text replaced by `inserted` bytes
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Edit {
+    pub start: usize,
+    pub end: usize,
+    pub inserted: usize,
+}
+
+impl Edit {
+    /// Offset in the new text of an offset of the old text after the edit
+    fn shift(&self, offset: usize) -> usize {
+        offset - (self.end - self.start) + self.inserted
+    }
+}
+
+pub struct Reparse {
+    pub tree: Node,
+    /// nodes taken over from the old tree instead of being parsed again
+    pub reused: usize,
+}
+
+impl Node {
+    /// Number of nodes of the tree
+    pub fn size(&self) -> usize {
+        1 + self.children.iter().map(Node::size).sum::<usize>()
+    }
+}
+
+/// Reparsing in the style of Wagner and Graham, the old tree is the input next to the new tokens and a subtree
+/// untouched by the edit is pushed as a whole when the parser is in the state it was pushed in before
+/// https://doi.org/10.1145/293677.293678
+struct Reparser<'old, 'machine, Machine> {
+    machine: &'machine Machine,
+    edit: Edit,
+    tokens: &'old [Token],
+    /// spans of the leaves of the old tree, in order
+    old_leaves: Vec<Span>,
+    /// subtrees of the old tree not passed yet, the leftmost last
+    pending: Vec<&'old Node>,
+    next: usize,
+    reused: usize,
+}
+
+impl<'old, 'machine, 'a, Machine: IStateMachine<'a>> Reparser<'old, 'machine, Machine> {
+    /// A subtree is untouched when the edit is after the terminals the parser looked ahead at
+    /// while building it, or before the subtree
+    fn untouched(&self, node: &Node) -> bool {
+        if node.span.start == node.span.end {
+            return false;
+        }
+        let after = self.old_leaves.partition_point(|leaf| leaf.start.offset < node.span.end.offset);
+        let lookahead_end = self.old_leaves.get(after + self.machine.lookahead() - 1).map_or(usize::MAX, |leaf| leaf.end.offset);
+        lookahead_end < self.edit.start || node.span.start.offset > self.edit.end
+    }
+
+    /// Offsets of an untouched subtree in the new text
+    fn moved(&self, node: &Node) -> (usize, usize) {
+        match node.span.start.offset > self.edit.end {
+            true => (self.edit.shift(node.span.start.offset), self.edit.shift(node.span.end.offset)),
+            false => (node.span.start.offset, node.span.end.offset),
+        }
+    }
+
+    /// Untouched variable of the old tree starting at the next token, the subtrees in front of it are dropped
+    /// and touched ones are broken down into their children
+    fn candidate(&mut self) -> Option<&'old Node> {
+        let position = self.tokens.get(self.next)?.span.start.offset;
+        while let Some(node) = self.pending.last().copied() {
+            if self.untouched(node) {
+                let (start, end) = self.moved(node);
+                match (start, end) {
+                    (start, _) if start > position => return None,
+                    (start, _) if start == position && matches!(node.symbol, MixedChar::Variable(_)) => return Some(node),
+                    (_, end) if end <= position => {
+                        self.pending.pop();
+                        continue;
+                    }
+                    _ => {}
+                }
+            }
+            self.break_down();
+        }
+        None
+    }
+
+    fn break_down(&mut self) {
+        if let Some(node) = self.pending.pop() {
+            self.pending.extend(node.children.iter().rev());
+        }
+    }
+
+    /// Copy of an old subtree with the spans of the new tokens from `self.next` on, `None` when its leaves
+    /// are not the terminals of the new tokens
+    fn respan(&self, node: &Node, next: &mut usize) -> Option<Node> {
+        if let MixedChar::Terminal(terminal) = node.symbol {
+            let token = self.tokens.get(*next).filter(|token| token.terminal == terminal)?;
+            *next += 1;
+            return Some(Node { span: token.span, ..node.clone() });
+        }
+        let children = node.children.iter().map(|child| self.respan(child, next)).collect::<Option<Vec<Node>>>()?;
+        // a variable deriving nothing sits right before the next terminal
+        let span = match (children.first(), children.last()) {
+            (Some(first), Some(last)) => first.span.cover(&last.span),
+            _ => self.tokens.get(*next).map_or_else(|| Span::point(self.tokens.last().map(|token| token.span.end).unwrap_or_default()), |token| Span::point(token.span.start)),
+        };
+        Some(Node { symbol: node.symbol, span, state: node.state, children })
+    }
+
+    fn span(&self) -> Span {
+        self.tokens.get(self.next).map_or_else(|| Span::point(self.tokens.last().map(|token| token.span.end).unwrap_or_default()), |token| token.span)
+    }
+
+    fn run(mut self) -> Result<Reparse, SyntaxError> {
+        let mut states = vec![0];
+        let mut nodes: Vec<Node> = vec![];
+        loop {
+            let state = *states.last().unwrap();
+            let rest: Vec<Terminal> = self.tokens[self.next..].iter()
+                .take(self.machine.lookahead())
+                .map(|token| token.terminal)
+                .chain([Terminal::end()])
+                .collect();
+            match self.machine.lookahead_action(state, &rest) {
+                Action::Accept => return Ok(Reparse { tree: nodes.pop().unwrap(), reused: self.reused }),
+                Action::Reject => return Err(SyntaxError { state, found: rest[0], span: self.span() }),
+                Action::Reduce(rule) => {
+                    let (variable, pop_count) = (self.machine.rules()[rule].clause, self.machine.rules()[rule].len());
+                    states.truncate(states.len() - pop_count);
+                    let children = nodes.split_off(nodes.len() - pop_count);
+                    let span = match (children.first(), children.last()) {
+                        (Some(first), Some(last)) => first.span.cover(&last.span),
+                        _ => Span::point(self.span().start),
+                    };
+                    let below = *states.last().unwrap();
+                    nodes.push(Node { symbol: MixedChar::Variable(variable), span, state: below, children });
+                    states.push(self.machine.reduce_state(below, variable));
+                }
+                Action::Shift(next) => {
+                    // the reductions the first terminal of a subtree calls for are done, the subtree can be pushed
+                    // when the parser is where it was before
+                    if let Some(candidate) = self.candidate() {
+                        let MixedChar::Variable(variable) = candidate.symbol else {
+                            unreachable!();
+                        };
+                        let mut after = self.next;
+                        let goto = self.machine.reduce_state(state, variable);
+                        match (candidate.state == state && goto != 0).then(|| self.respan(candidate, &mut after)).flatten() {
+                            Some(node) => {
+                                self.pending.pop();
+                                self.reused += node.size();
+                                self.next = after;
+                                nodes.push(node);
+                                states.push(goto);
+                            }
+                            None => self.break_down(),
+                        }
+                        continue;
+                    }
+                    // only the end of input is left, an lr(0) machine shifts it before accepting and it is not in the tree
+                    if let Some(token) = self.tokens.get(self.next) {
+                        nodes.push(Node { symbol: MixedChar::Terminal(token.terminal), span: token.span, state, children: vec![] });
+                        self.next += 1;
+                    }
+                    states.push(next);
+                }
+            }
+        }
+    }
+}
+
+/// Parses `tokens`, the new text after `edit`, taking over the subtrees of `old` the edit did not touch
+pub fn reparse<'a, Machine>(machine: &Machine, old: &Node, edit: Edit, tokens: &[Token]) -> Result<Reparse, SyntaxError>
+    where Machine: IStateMachine<'a> {
+    let mut old_leaves = vec![];
+    let mut stack = vec![old];
+    while let Some(node) = stack.pop() {
+        match node.symbol {
+            MixedChar::Terminal(_) => old_leaves.push(node.span),
+            MixedChar::Variable(_) => stack.extend(node.children.iter().rev()),
+        }
+    }
+    Reparser { machine, edit, tokens, old_leaves, pending: vec![old], next: 0, reused: 0 }.run()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{reparse, Edit};
+    use crate::lexer::{self, Lexer, Token};
+    use crate::parsing::tests::{lr_one, lr_zero, EXPRESSION};
+    use crate::parsing::ParsingProcess;
+    use crate::parsing_table::IStateMachine;
+    use crate::syntax::Rule;
+
+    const SOURCE: &str = "%i [a-z][a-z0-9]*\n%n [0-9]+\n%skip [ \\t]+\nE:E+T\nE:T\nT:i\nT:n\nT:(E)\n";
+
+    fn lexer() -> Lexer {
+        let rules: Vec<Rule> = SOURCE.lines().filter_map(|line| line.try_into().ok()).collect();
+        Lexer::from_source(SOURCE, &rules).unwrap().unwrap()
+    }
+
+    /// Replaces `start..end` of `text` by `inserted`, checks the reparsed tree against a full parse
+    /// of the new text and gives the number of reused nodes
+    fn reused<'a, Machine>(machine: &Machine, tokens: impl Fn(&str) -> Vec<Token>, text: &str, start: usize, end: usize, inserted: &str) -> usize
+        where Machine: IStateMachine<'a> {
+        let old = ParsingProcess::from_tokens(&tokens(text)).parse(machine).unwrap();
+        let edited = format!("{}{}{}", &text[..start], inserted, &text[end..]);
+        let new_tokens = tokens(&edited);
+        let reparsed = reparse(machine, &old, Edit { start, end, inserted: inserted.len() }, &new_tokens).unwrap();
+        assert_eq!(Ok(reparsed.tree), ParsingProcess::from_tokens(&new_tokens).parse(machine), "{:?}", edited);
+        reparsed.reused
+    }
+
+    #[test]
+    fn insertion() {
+        let (machine, lexer) = (lr_one(&["E:E+T", "E:T", "T:i", "T:n", "T:(E)"]), lexer());
+        let tokens = |text: &str| lexer.tokens(text).unwrap();
+        // E -> E + T over `a + b` is followed by a `+` before the edit
+        assert_eq!(reused(&machine, tokens, "a + b + c", 9, 9, " + d"), 7);
+        assert_eq!(reused(&machine, tokens, "a + b + c", 0, 0, "(x) + "), 4);
+        assert_eq!(reused(&machine, tokens, "a + (b + 12) + c", 16, 16, " + 7"), 15);
+    }
+
+    #[test]
+    fn deletion() {
+        let (machine, lexer) = (lr_one(&["E:E+T", "E:T", "T:i", "T:n", "T:(E)"]), lexer());
+        let tokens = |text: &str| lexer.tokens(text).unwrap();
+        assert_eq!(reused(&machine, tokens, "a + b + c + d", 9, 13, ""), 7);
+        reused(&machine, tokens, "a + (b + 12) + c", 4, 14, "");
+    }
+
+    #[test]
+    fn tokens_merged_at_the_edit() {
+        let (machine, lexer) = (lr_one(&["E:E+T", "E:T", "T:i", "T:n", "T:(E)"]), lexer());
+        let tokens = |text: &str| lexer.tokens(text).unwrap();
+        // `ab` and `cd` become the single identifier `abcd`
+        reused(&machine, tokens, "x + ab + cd", 6, 9, "");
+        // the identifier `b` grows into `b12` next to an untouched number
+        reused(&machine, tokens, "a + b + 34", 5, 5, "12");
+    }
+
+    #[test]
+    fn lr_zero_reparse() {
+        let machine = lr_zero(&EXPRESSION);
+        let tokens = |text: &str| lexer::characters(text.chars()).collect::<Vec<Token>>();
+        assert_eq!(reused(&machine, tokens, "a+a+a", 5, 5, "+a"), 7);
+        reused(&machine, tokens, "a+a+a", 1, 3, "");
+    }
+}
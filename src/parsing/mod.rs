@@ -0,0 +1,336 @@
+pub mod stream;
+pub mod push;
+pub mod incremental;
+pub mod trace;
+
+use std::fmt::Display;
+
+use crate::diagnostic::Style;
+use crate::lexer::{Location, Span, Token};
+use crate::parsing_table::{Action, IStateMachine};
+use crate::syntax::{self, MixedChar, MixedString, Terminal, TerminalString, Variable};
+
+/// Node of the parse tree, a shifted terminal or a reduced variable covering its children
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Node {
+    pub symbol: MixedChar,
+    pub span: Span,
+    /// state on top of the stack when the node was pushed, a reparse can only push the node again in this state
+    pub state: usize,
+    pub children: Vec<Node>,
+}
+
+/// The next terminal has no action in the current state
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SyntaxError {
+    pub state: usize,
+    pub found: Terminal,
+    pub span: Span,
+}
+
+#[derive(Clone)]
+pub struct ParsingProcess{
+    input: TerminalString,
+    string_index: usize,
+    state_index: usize,
+    output: MixedString,
+    stack: Vec<usize>,
+    /// where every terminal of the input came from
+    spans: Vec<Span>,
+    /// subtree of every symbol of the output
+    nodes: Vec<Node>,
+}
+
+impl  ParsingProcess{
+    pub fn new(input: &[char]) -> ParsingProcess {
+        let locations = Location::of_chars(input);
+        let spans = input.iter().enumerate()
+            .map(|(index, symbol)| match *symbol == syntax::END_TERMINAL {
+                true => Span::point(locations[index]),
+                false => Span { start: locations[index], end: locations[index + 1] },
+            })
+            .collect();
+        let input = TerminalString::from(input);
+
+        ParsingProcess { input, string_index: 0, state_index: 0, output: MixedString::new(), stack: vec![0], spans, nodes: vec![] }
+    }
+
+    /// Parses the tokens of a lexer, the end of input is added after the last one
+    pub fn from_tokens(tokens: &[Token]) -> ParsingProcess {
+        let mut input = TerminalString::new();
+        let mut spans: Vec<Span> = tokens.iter().map(|token| token.span).collect();
+        tokens.iter().for_each(|token| input.push_terminal(token.terminal));
+        input.push_terminal(Terminal::end());
+        spans.push(Span::point(spans.last().map_or_else(Location::default, |span| span.end)));
+
+        ParsingProcess { input, string_index: 0, state_index: 0, output: MixedString::new(), stack: vec![0], spans, nodes: vec![] }
+    }
+
+    /// Span of the next terminal of the input
+    pub fn span(&self) -> Span {
+        self.spans.get(self.string_index).copied().unwrap_or_default()
+    }
+
+    /// Subtrees of the symbols on the stack
+    pub fn nodes(&self) -> &[Node] {
+        &self.nodes
+    }
+
+    /// States on the stack, the current one last
+    pub fn states(&self) -> &[usize] {
+        &self.stack
+    }
+
+    /// The error at the next terminal
+    pub fn error(&self) -> SyntaxError {
+        SyntaxError { state: self.state_index, found: self.input.get(self.string_index).unwrap_or(Terminal::end()), span: self.span() }
+    }
+
+    /// Tree of the start variable once the machine accepts, an lr(0) machine has shifted the end of input
+    /// on top of it
+    fn root(mut self) -> Node {
+        if self.nodes.last().is_some_and(|node| node.symbol == MixedChar::Terminal(Terminal::end())) {
+            self.nodes.pop();
+        }
+        self.nodes.pop().unwrap()
+    }
+}
+impl <'a> ParsingProcess  {
+    fn action<Machine: IStateMachine<'a>>(&self, machine: &Machine) -> Action {
+        machine.lookahead_action(self.state_index, &self.input[self.string_index..])
+    }
+
+    /// Shifts or reduces, `on_reduce` gets the variable and the children of every reduction
+    fn apply<Machine, F>(&mut self, machine: &Machine, action: Action, on_reduce: &mut F)
+        where Machine: IStateMachine<'a>, F: FnMut(Variable, &[Node]) {
+        match action {
+            Action::Accept | Action::Reject => {}
+            Action::Shift(next) => {
+                let terminal = self.input.get(self.string_index).unwrap();
+                self.output.push_terminal(terminal);
+                self.nodes.push(Node { symbol: MixedChar::Terminal(terminal), span: self.span(), state: self.state_index, children: vec![] });
+                self.string_index+=1;
+                self.state_index = next;
+                self.stack.push(self.state_index);
+            },
+            Action::Reduce(rule) => {
+                let (variable, pop_count) = (machine.rules()[rule].clause, machine.rules()[rule].len());
+                for _ in 0..pop_count{
+                    self.output.pop();
+                    self.stack.pop();
+                }
+                let children = self.nodes.split_off(self.nodes.len() - pop_count);
+                // a variable deriving nothing sits right before the next terminal
+                let span = match (children.first(), children.last()) {
+                    (Some(first), Some(last)) => first.span.cover(&last.span),
+                    _ => Span::point(self.span().start),
+                };
+                on_reduce(variable, &children);
+                let state = *self.stack.last().unwrap();
+                self.nodes.push(Node { symbol: MixedChar::Variable(variable), span, state, children });
+                self.output.push_variable(variable);
+                self.state_index = machine.reduce_state(*self.stack.last().unwrap(), variable);
+                self.stack.push(self.state_index);
+            }
+        }
+    }
+
+    #[allow(unused)]
+    pub fn run<Machine: IStateMachine<'a>>(&mut self, machine: &Machine) -> Option<bool>{
+        match self.action(machine) {
+            Action::Accept => Some(true),
+            Action::Reject => Some(false),
+            action => {
+                self.apply(machine, action, &mut |_, _| {});
+                None
+            }
+        }
+    }
+
+    /// Parses the rest of the input, `on_reduce` is the semantic action run with the variable
+    /// and the children of every reduction. Gives the tree of the start variable
+    pub fn parse_with<Machine, F>(mut self, machine: &Machine, mut on_reduce: F) -> Result<Node, SyntaxError>
+        where Machine: IStateMachine<'a>, F: FnMut(Variable, &[Node]) {
+        loop {
+            match self.action(machine) {
+                Action::Accept => return Ok(self.root()),
+                Action::Reject => return Err(self.error()),
+                action => self.apply(machine, action, &mut on_reduce),
+            }
+        }
+    }
+
+    pub fn parse<Machine: IStateMachine<'a>>(self, machine: &Machine) -> Result<Node, SyntaxError> {
+        self.parse_with(machine, |_, _| {})
+    }
+
+    pub fn get_next<Machine: IStateMachine<'a>>(&self, machine: &Machine) -> Option<Self>{
+        let action = self.action(machine);
+        if let Action::Accept | Action::Reject = action {
+            return None;
+        }
+        let mut next_step = self.clone();
+        next_step.apply(machine, action, &mut |_, _| {});
+        Some(next_step)
+    }
+
+
+    pub fn display<Machine: IStateMachine<'a>>(&self, machine: &Machine) -> PrintingString {
+        PrintingString { step: self.step(machine) }
+    }
+}
+
+/// Step of the parse as a row of the interactive trace
+pub struct PrintingString {
+    step: trace::Step,
+}
+use prettytable::{Cell, Row, Table};
+
+/// Reverse video over the part of the stack a reduction pops
+const POPPED: &str = "7";
+
+/// `[a, b, c]` with the last `popped` items painted
+fn listed(items: Vec<String>, popped: usize, style: Style) -> String {
+    let (kept, popped) = items.split_at(items.len() - popped);
+    match popped.is_empty() {
+        true => format!("[{}]", kept.join(", ")),
+        false => {
+            let kept: String = kept.iter().map(|item| format!("{}, ", item)).collect();
+            format!("[{}{}]", kept, style.paint(POPPED, &popped.join(", ")))
+        }
+    }
+}
+
+impl PrintingString {
+    /// The row of the step, `style` paints the states and symbols a reduction pops
+    pub fn get_row(&self, index: usize, style: Style) -> Row{
+        let stack = self.step.stack.iter().map(usize::to_string).collect();
+        let symbols = self.step.symbols.data.iter().map(|symbol| format!("'{}'", symbol)).collect();
+        Row::new(vec![
+            Cell::new(&index.to_string()),
+            Cell::new(&format!("{:?}",self.step.state)),
+            Cell::new(&listed(stack, self.step.popped(), style)),
+            Cell::new(&listed(symbols, self.step.popped(), style)),
+            Cell::new(&format!("{:?}",self.step.input)),
+            Cell::new(&self.step.describe()),
+        ])
+    }
+
+}
+
+/// Step by step table of a parse, the layout of the interactive trace. The part of the stack the last step pops
+/// is painted with `style`
+pub fn history_table<'a, Machine: IStateMachine<'a>>(machine: &'a Machine, history: &'a [ParsingProcess], style: Style) -> Table {
+    let mut table = Table::new();
+    table.add_row(Row::new(vec![
+        Cell::new("Step"),
+        Cell::new("State"),
+        Cell::new("Stack"),
+        Cell::new("Output"),
+        Cell::new("Input"),
+        Cell::new("Action"),
+
+    ]));
+    for (step, parser) in history.iter().enumerate() {
+        let style = match step + 1 == history.len() {
+            true => style,
+            false => Style::Plain,
+        };
+        table.add_row(parser.display(machine).get_row(step, style));
+    }
+    table
+}
+
+impl Display for PrintingString{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {:?} {:?} {:?} {}",self.step.state, self.step.stack ,self.step.symbols ,self.step.input, self.step.describe())
+    }
+}
+impl Display for Node {
+    /// One line per node, indented by depth, with the span it covers
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut pending = vec![(self, 0)];
+        while let Some((node, depth)) = pending.pop() {
+            writeln!(f, "{:width$}{} {}", "", node.symbol, node.span, width = depth * 2)?;
+            pending.extend(node.children.iter().rev().map(|child| (child, depth + 1)));
+        }
+        Ok(())
+    }
+}
+
+impl Display for SyntaxError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.found == Terminal::end() {
+            true => write!(f, "{}: unexpected end of input in state {}", self.span.start, self.state),
+            false => write!(f, "{}: unexpected {} in state {}", self.span.start, self.found, self.state),
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use crate::itemset::{LRkItemSets, LROneItemSets, LRZeroItemSets};
+    use crate::parsing::ParsingProcess;
+    use crate::parsing_table::{lr_k, lr_one, lr_zero, IStateMachine};
+    use crate::syntax;
+
+    pub const EXPRESSION: [&str; 3] = ["E:E+T", "E:T", "T:a"];
+
+    pub const EXPRESSION_TREE: &str = "\
+E 1:1..1:4
+  E 1:1..1:2
+    T 1:1..1:2
+      a 1:1..1:2
+  + 1:2..1:3
+  T 1:3..1:4
+    a 1:3..1:4
+";
+
+    pub fn lr_zero(rules: &[&str]) -> lr_zero::StateMachine {
+        let mut itemsets = LRZeroItemSets::new('E');
+        for rule in rules {
+            assert!(itemsets.add_from_string(rule));
+        }
+        itemsets.generate_next();
+        lr_zero::StateMachine::from_itemset(&itemsets)
+    }
+
+    pub fn lr_one(rules: &[&str]) -> lr_one::StateMachine {
+        let mut itemsets = LROneItemSets::new('E');
+        for rule in rules {
+            assert!(itemsets.add_from_string(rule));
+        }
+        itemsets.generate_next();
+        lr_one::StateMachine::from_itemset(&itemsets)
+    }
+
+    pub fn lr_k(rules: &[&str], k: usize) -> lr_k::StateMachine {
+        let mut itemsets = LRkItemSets::new('E', k);
+        for rule in rules {
+            assert!(itemsets.add_from_string(rule));
+        }
+        itemsets.generate_next();
+        lr_k::StateMachine::from_itemset(&itemsets)
+    }
+
+    pub fn process(input: &str) -> ParsingProcess {
+        let input: Vec<char> = input.chars().chain([syntax::END_TERMINAL]).collect();
+        ParsingProcess::new(&input)
+    }
+
+    #[test]
+    fn parse_gives_the_start_variable() {
+        let tree = process("a+a").parse(&lr_zero(&EXPRESSION)).unwrap();
+        assert_eq!(tree.to_string(), EXPRESSION_TREE);
+        let tree = process("a+a").parse(&lr_one(&EXPRESSION)).unwrap();
+        assert_eq!(tree.to_string(), EXPRESSION_TREE);
+    }
+
+    #[test]
+    fn parse_stops_at_the_first_error() {
+        let error = process("aa+a").parse(&lr_zero(&EXPRESSION)).unwrap_err();
+        assert_eq!((error.found.symbol, error.span.to_string()), ('a', "1:2..1:3".to_string()));
+        let error = process("a+").parse(&lr_one(&EXPRESSION)).unwrap_err();
+        assert_eq!((error.found.symbol, error.span.to_string()), (syntax::END_TERMINAL, "1:3..1:3".to_string()));
+    }
+}
@@ -0,0 +1,147 @@
+use crate::lexer::{Span, Token};
+use crate::parsing::{Node, ParsingProcess, SyntaxError};
+use crate::parsing_table::{Action, IStateMachine};
+use crate::syntax::{MixedString, Terminal, TerminalString, Variable};
+
+/// A reduction done while taking a token
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Reduction {
+    pub variable: Variable,
+    pub span: Span,
+    /// number of symbols popped
+    pub length: usize,
+    /// state entered by the goto on the variable
+    pub state: usize,
+}
+
+/// Parser fed one token at a time by the caller, the stack of the `ParsingProcess` is kept between calls
+/// so the partial parse can be looked at after every token
+pub struct PushParser<'machine, Machine> {
+    machine: &'machine Machine,
+    process: ParsingProcess,
+}
+
+impl<'machine, 'a, Machine: IStateMachine<'a>> PushParser<'machine, Machine> {
+    pub fn new(machine: &'machine Machine) -> Self {
+        let process = ParsingProcess { input: TerminalString::new(), string_index: 0, state_index: 0, output: MixedString::new(), stack: vec![0], spans: vec![], nodes: vec![] };
+        Self { machine, process }
+    }
+
+    pub fn process(&self) -> &ParsingProcess {
+        &self.process
+    }
+
+    /// Runs the machine while it can see as many terminals as it looks ahead at, or to the end once `finished`
+    fn run(&mut self, finished: bool) -> Result<Vec<Reduction>, SyntaxError> {
+        let mut reductions = vec![];
+        while finished || self.process.input.data.len() - self.process.string_index >= self.machine.lookahead() {
+            let action = self.process.action(self.machine);
+            match action {
+                Action::Accept => break,
+                Action::Reject => return Err(self.process.error()),
+                Action::Shift(_) => self.process.apply(self.machine, action, &mut |_, _| {}),
+                Action::Reduce(rule) => {
+                    self.process.apply(self.machine, action, &mut |_, _| {});
+                    let span = self.process.nodes.last().unwrap().span;
+                    let (variable, length) = (self.machine.rules()[rule].clause, self.machine.rules()[rule].len());
+                    reductions.push(Reduction { variable, span, length, state: self.process.state_index });
+                }
+            }
+        }
+        Ok(reductions)
+    }
+
+    /// Takes the next token and does every reduction it decides. When the parser has no action for it
+    /// the parser is put back as it was before the token, reductions made on the way included
+    pub fn push(&mut self, token: Token) -> Result<Vec<Reduction>, SyntaxError> {
+        // a default reduction or a merged lalr state can reduce before the token is rejected
+        let before = self.process.clone();
+        self.process.input.push_terminal(token.terminal);
+        self.process.spans.push(token.span);
+        let result = self.run(false);
+        if result.is_err() {
+            self.process = before;
+        }
+        result
+    }
+
+    /// Ends the input, gives the reductions left and the parse tree
+    pub fn finish(mut self) -> Result<(Vec<Reduction>, Node), SyntaxError> {
+        let end = self.process.spans.last().map(|span| span.end).unwrap_or_default();
+        self.process.input.push_terminal(Terminal::end());
+        self.process.spans.push(Span::point(end));
+        let reductions = self.run(true)?;
+        Ok((reductions, self.process.root()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PushParser;
+    use crate::itemset::LROneItemSets;
+    use crate::lexer;
+    use crate::parsing::tests::{lr_one, lr_zero, EXPRESSION, EXPRESSION_TREE};
+    use crate::parsing_table::{compressed, IStateMachine};
+
+    fn push_all<'machine, 'a, Machine: IStateMachine<'a>>(machine: &'machine Machine, input: &str) -> PushParser<'machine, Machine> {
+        let mut parser = PushParser::new(machine);
+        for token in lexer::characters(input.chars()) {
+            parser.push(token).unwrap();
+        }
+        parser
+    }
+
+    #[test]
+    fn finish_gives_the_start_variable() {
+        let lr_zero = lr_zero(&EXPRESSION);
+        let (_, tree) = push_all(&lr_zero, "a+a").finish().unwrap();
+        assert_eq!(tree.to_string(), EXPRESSION_TREE);
+        let lr_one = lr_one(&EXPRESSION);
+        let (reductions, tree) = push_all(&lr_one, "a+a").finish().unwrap();
+        assert_eq!(tree.to_string(), EXPRESSION_TREE);
+        // T -> a of the last a and E -> E + T wait for the end of input
+        assert_eq!(reductions.len(), 2);
+    }
+
+    #[test]
+    fn rejected_token_is_dropped() {
+        let lr_one = lr_one(&EXPRESSION);
+        let mut parser = PushParser::new(&lr_one);
+        let mut tokens = lexer::characters("a+)a".chars());
+        parser.push(tokens.next().unwrap()).unwrap();
+        parser.push(tokens.next().unwrap()).unwrap();
+        assert!(parser.push(tokens.next().unwrap()).is_err());
+        parser.push(tokens.next().unwrap()).unwrap();
+        let (_, tree) = parser.finish().unwrap();
+        assert_eq!(tree.span.to_string(), "1:1..1:5");
+    }
+
+    #[test]
+    fn unfinished_input_is_an_error() {
+        let lr_zero = lr_zero(&EXPRESSION);
+        let error = push_all(&lr_zero, "a+").finish().err().unwrap();
+        assert_eq!(error.span.to_string(), "1:3..1:3");
+    }
+
+    #[test]
+    fn reductions_before_a_rejected_token_are_undone() {
+        let mut itemsets = LROneItemSets::new('E');
+        for rule in ["E:E+T", "E:T", "T:a", "T:ab"] {
+            assert!(itemsets.add_from_string(rule));
+        }
+        itemsets.generate_next();
+        let machine = compressed::StateMachine::from_itemset(&itemsets);
+        let mut parser = PushParser::new(&machine);
+        let mut tokens = lexer::characters("a)b+a".chars());
+        parser.push(tokens.next().unwrap()).unwrap();
+        let stack = parser.process().states().to_vec();
+        // T -> a is the default reduction after a, it is done before ) is found to be wrong
+        assert!(parser.push(tokens.next().unwrap()).is_err());
+        assert_eq!(parser.process().states(), stack);
+        for token in tokens {
+            parser.push(token).unwrap();
+        }
+        let (_, tree) = parser.finish().unwrap();
+        assert_eq!(tree.span.to_string(), "1:1..1:6");
+    }
+}
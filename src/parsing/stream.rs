@@ -0,0 +1,132 @@
+use std::collections::VecDeque;
+
+use crate::lexer::{Location, Span, Token};
+use crate::parsing::{Node, SyntaxError};
+use crate::parsing_table::{Action, IStateMachine};
+use crate::syntax::{MixedChar, Terminal, Variable};
+
+/// Parses tokens pulled one at a time from an iterator, only the tokens the machine looks ahead at are held,
+/// so the input is never read as a whole and a syntax error stops reading
+pub struct StreamParser<'machine, Machine, Tokens: Iterator<Item = Token>> {
+    machine: &'machine Machine,
+    tokens: Tokens,
+    lookahead: VecDeque<Token>,
+    /// end of the last token pulled, where the end of input sits
+    end: Location,
+    /// the iterator returned `None`
+    exhausted: bool,
+}
+
+impl<'machine, 'a, Machine, Tokens> StreamParser<'machine, Machine, Tokens>
+    where Machine: IStateMachine<'a>, Tokens: Iterator<Item = Token> {
+    pub fn new(machine: &'machine Machine, tokens: Tokens) -> Self {
+        Self { machine, tokens, lookahead: VecDeque::new(), end: Location::default(), exhausted: false }
+    }
+
+    /// Pulls tokens until the machine can see the next `lookahead` terminals or the input ends
+    fn fill(&mut self) {
+        while !self.exhausted && self.lookahead.len() < self.machine.lookahead() {
+            match self.tokens.next() {
+                Some(token) => {
+                    self.end = token.span.end;
+                    self.lookahead.push_back(token);
+                }
+                None => self.exhausted = true,
+            }
+        }
+    }
+
+    /// Span of the next terminal, a point after the last token at the end of input
+    fn span(&self) -> Span {
+        self.lookahead.front().map_or(Span::point(self.end), |token| token.span)
+    }
+
+    /// Parses the whole input, `shift` gives the value of every token and `reduce` the value of a variable
+    /// from its span and the values of its children, both also get the state the value is pushed on.
+    /// Gives the value of the start variable
+    pub fn fold<Value, Shift, Reduce>(mut self, mut shift: Shift, mut reduce: Reduce) -> Result<Value, SyntaxError>
+        where Shift: FnMut(Token, usize) -> Value, Reduce: FnMut(Variable, Span, Vec<Value>, usize) -> Value {
+        let mut states = vec![0];
+        let mut values: Vec<(Span, Value)> = vec![];
+        loop {
+            self.fill();
+            let rest: Vec<Terminal> = self.lookahead.iter().map(|token| token.terminal).chain([Terminal::end()]).collect();
+            let state = *states.last().unwrap();
+            match self.machine.lookahead_action(state, &rest) {
+                Action::Accept => return Ok(values.pop().unwrap().1),
+                Action::Reject => return Err(SyntaxError { state, found: rest[0], span: self.span() }),
+                Action::Shift(next) => {
+                    // only the end of input is left, an lr(0) machine shifts it before accepting and it has no value
+                    if let Some(token) = self.lookahead.pop_front() {
+                        values.push((token.span, shift(token, state)));
+                    }
+                    states.push(next);
+                }
+                Action::Reduce(rule) => {
+                    let (variable, pop_count) = (self.machine.rules()[rule].clause, self.machine.rules()[rule].len());
+                    states.truncate(states.len() - pop_count);
+                    let (spans, children): (Vec<Span>, Vec<Value>) = values.split_off(values.len() - pop_count).into_iter().unzip();
+                    // a variable deriving nothing sits right before the next terminal
+                    let span = match (spans.first(), spans.last()) {
+                        (Some(first), Some(last)) => first.cover(last),
+                        _ => Span::point(self.span().start),
+                    };
+                    let below = *states.last().unwrap();
+                    values.push((span, reduce(variable, span, children, below)));
+                    states.push(self.machine.reduce_state(below, variable));
+                }
+            }
+        }
+    }
+
+    /// Parse tree of the input
+    pub fn parse(self) -> Result<Node, SyntaxError> {
+        self.fold(
+            |token, state| Node { symbol: MixedChar::Terminal(token.terminal), span: token.span, state, children: vec![] },
+            |variable, span, children, state| Node { symbol: MixedChar::Variable(variable), span, state, children },
+        )
+    }
+
+    /// Checks the input without keeping a tree, gives the number of reductions
+    pub fn recognize(self) -> Result<usize, SyntaxError> {
+        let mut reductions = 0;
+        self.fold(|_, _| (), |_, _, _, _| reductions += 1)?;
+        Ok(reductions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::StreamParser;
+    use crate::lexer::{self, Lexer};
+    use crate::parsing::tests::{lr_one, lr_zero, EXPRESSION, EXPRESSION_TREE};
+    use crate::syntax::Rule;
+
+    #[test]
+    fn lr_zero_and_lr_one_give_the_start_variable() {
+        let lr_zero = lr_zero(&EXPRESSION);
+        assert_eq!(StreamParser::new(&lr_zero, lexer::characters("a+a".chars())).recognize(), Ok(4));
+        assert_eq!(StreamParser::new(&lr_zero, lexer::characters("a+a".chars())).parse().unwrap().to_string(), EXPRESSION_TREE);
+        let lr_one = lr_one(&EXPRESSION);
+        assert_eq!(StreamParser::new(&lr_one, lexer::characters("a+a".chars())).recognize(), Ok(4));
+        assert_eq!(StreamParser::new(&lr_one, lexer::characters("a+a".chars())).parse().unwrap().to_string(), EXPRESSION_TREE);
+    }
+
+    #[test]
+    fn error_at_the_end_of_input() {
+        let error = StreamParser::new(&lr_zero(&EXPRESSION), lexer::characters("a+".chars())).recognize().unwrap_err();
+        assert_eq!(error.span.to_string(), "1:3..1:3");
+    }
+
+    #[test]
+    fn lines_lexed_on_their_own() {
+        let source = "%i [a-z][a-z0-9]*\n%n [0-9]+\n%skip [ \\t]+\nE:E+T\nE:T\nT:i\nT:n\n";
+        let rules: Vec<Rule> = source.lines().filter_map(|line| line.try_into().ok()).collect();
+        let lexer = Lexer::from_source(source, &rules).unwrap().unwrap();
+        let lines = ["x1 + 42", "+ b"].map(String::from).into_iter();
+        let tokens: Vec<String> = lexer.lines(lines.clone()).map(|token| token.unwrap().span.to_string()).collect();
+        assert_eq!(tokens, ["1:1..1:3", "1:4..1:5", "1:6..1:8", "2:1..2:2", "2:3..2:4"]);
+        let tokens = lexer.lines(lines).map(Result::unwrap);
+        assert_eq!(StreamParser::new(&lr_one(&["E:E+T", "E:T", "T:i", "T:n"]), tokens).recognize(), Ok(6));
+    }
+}
@@ -0,0 +1,306 @@
+use std::fmt::Write;
+
+use crate::parsing::ParsingProcess;
+use crate::parsing_table::{Action, IStateMachine};
+use crate::syntax::{MixedString, Rule, Terminal};
+
+/// Configuration of the parser before a step and the action taken from it
+#[derive(Clone, Debug)]
+pub struct Step {
+    pub state: usize,
+    pub stack: Vec<usize>,
+    pub symbols: MixedString,
+    pub input: Vec<Terminal>,
+    pub action: Action,
+    /// rule of a reduction, its body is the top of the symbol stack
+    pub rule: Option<Rule>,
+    /// state the action pushes, the shifted state or the goto of the reduced variable
+    pub target: Option<usize>,
+}
+
+impl Step {
+    pub fn name(&self) -> &'static str {
+        match self.action {
+            Action::Accept => "accept",
+            Action::Reject => "reject",
+            Action::Shift(_) => "shift",
+            Action::Reduce(_) => "reduce",
+        }
+    }
+
+    /// The action with what it does, `shift 5` or `reduce A -> z, goto 7`
+    pub fn describe(&self) -> String {
+        match (self.rule_text(), self.target) {
+            (Some(rule), Some(target)) => format!("reduce {}, goto {}", rule, target),
+            (None, Some(target)) => format!("shift {}", target),
+            _ => self.name().to_string(),
+        }
+    }
+
+    /// Number of symbols and states the action pops off the stack
+    pub fn popped(&self) -> usize {
+        self.rule.as_ref().map_or(0, Rule::len)
+    }
+
+    pub fn rule_text(&self) -> Option<String> {
+        self.rule.as_ref().map(rule_text)
+    }
+}
+
+impl<'a> ParsingProcess {
+    pub fn step<Machine: IStateMachine<'a>>(&self, machine: &Machine) -> Step {
+        let action = self.action(machine);
+        let (rule, target) = match action {
+            Action::Shift(next) => (None, Some(next)),
+            Action::Reduce(rule) => {
+                let rule = machine.rules()[rule].clone();
+                let below = self.stack[self.stack.len() - 1 - rule.len()];
+                let target = machine.reduce_state(below, rule.clause);
+                (Some(rule), Some(target))
+            }
+            Action::Accept | Action::Reject => (None, None),
+        };
+        Step {
+            state: self.state_index,
+            stack: self.stack.clone(),
+            symbols: self.output.clone(),
+            input: self.input[self.string_index..].to_vec(),
+            action,
+            rule,
+            target,
+        }
+    }
+}
+
+/// The rule as `A -> x y`
+pub fn rule_text(rule: &Rule) -> String {
+    format!("{} -> {}", rule.clause, rule.output.to_string().trim_end())
+}
+
+/// Every step of a parse, one per process of the history
+pub fn steps<'a, Machine: IStateMachine<'a>>(machine: &Machine, history: &[ParsingProcess]) -> Vec<Step> {
+    history.iter().map(|process| process.step(machine)).collect()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    Json,
+    Csv,
+    Markdown,
+}
+
+impl Format {
+    /// `json`, `csv` or `md`, also taken from the extension of a file name
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.rsplit('.').next()? {
+            "json" => Some(Self::Json),
+            "csv" => Some(Self::Csv),
+            "md" | "markdown" => Some(Self::Markdown),
+            _ => None,
+        }
+    }
+}
+
+fn joined<T: ToString>(items: &[T]) -> String {
+    items.iter().map(ToString::to_string).collect::<Vec<String>>().join(" ")
+}
+
+fn json_string(text: &str) -> String {
+    let mut output = String::from("\"");
+    for symbol in text.chars() {
+        match symbol {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            symbol if symbol.is_control() => write!(output, "\\u{:04x}", symbol as u32).unwrap(),
+            symbol => output.push(symbol),
+        }
+    }
+    output.push('"');
+    output
+}
+
+fn json_array<T: ToString>(items: &[T], quoted: bool) -> String {
+    let items: Vec<String> = items.iter()
+        .map(|item| match quoted {
+            true => json_string(&item.to_string()),
+            false => item.to_string(),
+        })
+        .collect();
+    format!("[{}]", items.join(", "))
+}
+
+/// A field quoted when it holds a separator, a quote or a control character like a line break
+fn csv_field(text: &str) -> String {
+    match text.contains(|symbol: char| matches!(symbol, ',' | '"') || symbol.is_control()) {
+        true => format!("\"{}\"", text.replace('"', "\"\"")),
+        false => text.to_string(),
+    }
+}
+
+/// Every step of the trace, one object, line or table row per step
+pub fn export(steps: &[Step], format: Format) -> String {
+    let mut output = String::new();
+    match format {
+        Format::Json => {
+            output.push_str("[\n");
+            for (index, step) in steps.iter().enumerate() {
+                write!(
+                    output,
+                    "  {{\"step\": {}, \"state\": {}, \"stack\": {}, \"symbols\": {}, \"input\": {}, \"action\": {}, \"rule\": {}, \"target\": {}}}",
+                    index,
+                    step.state,
+                    json_array(&step.stack, false),
+                    json_array(&step.symbols.data, true),
+                    json_array(&step.input, true),
+                    json_string(step.name()),
+                    step.rule_text().map_or("null".to_string(), |rule| json_string(&rule)),
+                    step.target.map_or("null".to_string(), |target| target.to_string()),
+                ).unwrap();
+                output.push_str(if index + 1 < steps.len() { ",\n" } else { "\n" });
+            }
+            output.push_str("]\n");
+        }
+        Format::Csv => {
+            output.push_str("step,state,stack,symbols,input,action,rule,target\n");
+            for (index, step) in steps.iter().enumerate() {
+                let fields = [
+                    index.to_string(),
+                    step.state.to_string(),
+                    joined(&step.stack),
+                    joined(&step.symbols.data),
+                    joined(&step.input),
+                    step.name().to_string(),
+                    step.rule_text().unwrap_or_default(),
+                    step.target.map_or(String::new(), |target| target.to_string()),
+                ];
+                let fields: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+                writeln!(output, "{}", fields.join(",")).unwrap();
+            }
+        }
+        Format::Markdown => {
+            output.push_str("| Step | State | Stack | Symbols | Input | Action | Rule | Target |\n");
+            output.push_str("|---:|---:|---|---|---|---|---|---:|\n");
+            // characters markdown would read as formatting are escaped, control characters would break the row
+            let cell = |text: String| text.chars().fold(String::new(), |mut cell, symbol| {
+                match symbol {
+                    '\\' | '|' | '*' | '_' | '`' => write!(cell, "\\{}", symbol).unwrap(),
+                    symbol if symbol.is_control() => write!(cell, "\\u{:04x}", symbol as u32).unwrap(),
+                    symbol => cell.push(symbol),
+                }
+                cell
+            });
+            for (index, step) in steps.iter().enumerate() {
+                writeln!(
+                    output,
+                    "| {} | {} | {} | {} | {} | {} | {} | {} |",
+                    index,
+                    step.state,
+                    cell(joined(&step.stack)),
+                    cell(joined(&step.symbols.data)),
+                    cell(joined(&step.input)),
+                    step.name(),
+                    cell(step.rule_text().unwrap_or_default()),
+                    step.target.map_or(String::new(), |target| target.to_string()),
+                ).unwrap();
+            }
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{export, steps, Format, Step};
+    use crate::parsing::tests::{lr_one, EXPRESSION};
+    use crate::parsing::ParsingProcess;
+    use crate::parsing_table::Action;
+    use crate::syntax::{self, MixedChar, MixedString, Rule, Terminal};
+
+    fn trace(format: Format) -> String {
+        let machine = lr_one(&EXPRESSION);
+        let input: Vec<char> = "a+a".chars().chain([syntax::END_TERMINAL]).collect();
+        let mut history = vec![ParsingProcess::new(&input)];
+        while let Some(next) = history.last().unwrap().get_next(&machine) {
+            history.push(next);
+        }
+        export(&steps(&machine, &history), format)
+    }
+
+    /// Reduction of `T -> " ,` with a `|` and a bell character left in the input
+    fn special_characters() -> Vec<Step> {
+        vec![Step {
+            state: 3,
+            stack: vec![0, 5, 3],
+            symbols: MixedString { data: vec![MixedChar::Terminal(Terminal { symbol: '"' }), MixedChar::Terminal(Terminal { symbol: ',' })] },
+            input: vec![Terminal { symbol: '|' }, Terminal { symbol: '\u{7}' }, Terminal::end()],
+            action: Action::Reduce(1),
+            rule: Some(Rule::try_from("T:\",").unwrap()),
+            target: Some(4),
+        }]
+    }
+
+    #[test]
+    fn json() {
+        assert_eq!(trace(Format::Json), r#"[
+  {"step": 0, "state": 0, "stack": [0], "symbols": [], "input": ["a", "+", "a", "EOF"], "action": "shift", "rule": null, "target": 1},
+  {"step": 1, "state": 1, "stack": [0, 1], "symbols": ["a"], "input": ["+", "a", "EOF"], "action": "reduce", "rule": "T -> a", "target": 3},
+  {"step": 2, "state": 3, "stack": [0, 3], "symbols": ["T"], "input": ["+", "a", "EOF"], "action": "reduce", "rule": "E -> T", "target": 2},
+  {"step": 3, "state": 2, "stack": [0, 2], "symbols": ["E"], "input": ["+", "a", "EOF"], "action": "shift", "rule": null, "target": 4},
+  {"step": 4, "state": 4, "stack": [0, 2, 4], "symbols": ["E", "+"], "input": ["a", "EOF"], "action": "shift", "rule": null, "target": 1},
+  {"step": 5, "state": 1, "stack": [0, 2, 4, 1], "symbols": ["E", "+", "a"], "input": ["EOF"], "action": "reduce", "rule": "T -> a", "target": 5},
+  {"step": 6, "state": 5, "stack": [0, 2, 4, 5], "symbols": ["E", "+", "T"], "input": ["EOF"], "action": "reduce", "rule": "E -> E + T", "target": 2},
+  {"step": 7, "state": 2, "stack": [0, 2], "symbols": ["E"], "input": ["EOF"], "action": "accept", "rule": null, "target": null}
+]
+"#);
+    }
+
+    #[test]
+    fn csv() {
+        assert_eq!(trace(Format::Csv), "\
+step,state,stack,symbols,input,action,rule,target
+0,0,0,,a + a EOF,shift,,1
+1,1,0 1,a,+ a EOF,reduce,T -> a,3
+2,3,0 3,T,+ a EOF,reduce,E -> T,2
+3,2,0 2,E,+ a EOF,shift,,4
+4,4,0 2 4,E +,a EOF,shift,,1
+5,1,0 2 4 1,E + a,EOF,reduce,T -> a,5
+6,5,0 2 4 5,E + T,EOF,reduce,E -> E + T,2
+7,2,0 2,E,EOF,accept,,
+");
+    }
+
+    #[test]
+    fn markdown() {
+        assert_eq!(trace(Format::Markdown), "\
+| Step | State | Stack | Symbols | Input | Action | Rule | Target |
+|---:|---:|---|---|---|---|---|---:|
+| 0 | 0 | 0 |  | a + a EOF | shift |  | 1 |
+| 1 | 1 | 0 1 | a | + a EOF | reduce | T -> a | 3 |
+| 2 | 3 | 0 3 | T | + a EOF | reduce | E -> T | 2 |
+| 3 | 2 | 0 2 | E | + a EOF | shift |  | 4 |
+| 4 | 4 | 0 2 4 | E + | a EOF | shift |  | 1 |
+| 5 | 1 | 0 2 4 1 | E + a | EOF | reduce | T -> a | 5 |
+| 6 | 5 | 0 2 4 5 | E + T | EOF | reduce | E -> E + T | 2 |
+| 7 | 2 | 0 2 | E | EOF | accept |  |  |
+");
+    }
+
+    #[test]
+    fn escaped_special_characters() {
+        let steps = special_characters();
+        assert_eq!(export(&steps, Format::Json), r#"[
+  {"step": 0, "state": 3, "stack": [0, 5, 3], "symbols": ["\"", ","], "input": ["|", "\u0007", "EOF"], "action": "reduce", "rule": "T -> \" ,", "target": 4}
+]
+"#);
+        assert_eq!(export(&steps, Format::Csv), "\
+step,state,stack,symbols,input,action,rule,target
+0,3,0 5 3,\"\"\" ,\",\"| \u{7} EOF\",reduce,\"T -> \"\" ,\",4
+");
+        assert_eq!(export(&steps, Format::Markdown), "\
+| Step | State | Stack | Symbols | Input | Action | Rule | Target |
+|---:|---:|---|---|---|---|---|---:|
+| 0 | 3 | 0 5 3 | \" , | \\| \\u0007 EOF | reduce | T -> \" , | 4 |
+");
+    }
+}
@@ -0,0 +1,253 @@
+use std::collections::{BTreeSet, HashMap};
+use std::mem::size_of;
+
+use crate::itemset::LROneItemSets;
+use crate::parsing_table::{Action, Conflict, IStateMachine, lr_one};
+use crate::parsing_table::display::compressed::StateMachineDisplay;
+use crate::syntax::{self, MixedChar, Terminal, Variable};
+
+/// Sparse rows packed into one vector, as yacc does, entry (row, column) lives at `base[row] + column`
+/// when `check` there holds the row
+#[derive(Default)]
+pub struct Comb {
+    pub base: Vec<usize>,
+    pub value: Vec<i32>,
+    pub check: Vec<u32>,
+}
+
+impl Comb {
+    const FREE: u32 = u32::MAX;
+
+    /// Densest rows are placed first, each at the lowest displacement where none of its entries collide
+    fn pack(rows: &[Vec<(usize, i32)>]) -> Self {
+        let mut comb = Self { base: vec![0; rows.len()], value: vec![], check: vec![] };
+        let mut order: Vec<usize> = (0..rows.len()).collect();
+        order.sort_by_key(|row| std::cmp::Reverse(rows[*row].len()));
+        for row in order {
+            let entries = &rows[row];
+            if entries.is_empty() {
+                continue;
+            }
+            let base = (0..).find(|base| entries.iter().all(|(column, _)| {
+                comb.check.get(base + column).is_none_or(|owner| *owner == Self::FREE)
+            })).unwrap();
+            for (column, value) in entries {
+                if comb.check.len() <= base + column {
+                    comb.check.resize(base + column + 1, Self::FREE);
+                    comb.value.resize(base + column + 1, 0);
+                }
+                comb.check[base + column] = row as u32;
+                comb.value[base + column] = *value;
+            }
+            comb.base[row] = base;
+        }
+        comb
+    }
+
+    fn get(&self, row: usize, column: usize) -> Option<i32> {
+        let index = self.base[row] + column;
+        (self.check.get(index) == Some(&(row as u32))).then(|| self.value[index])
+    }
+
+    fn memory(&self) -> usize {
+        self.base.len() * size_of::<usize>() + self.value.len() * size_of::<i32>() + self.check.len() * size_of::<u32>()
+    }
+}
+
+/// LR(1) table with rule numbers instead of rules, a default reduction per state
+/// and the action and goto rows packed into comb vectors
+pub struct StateMachine {
+    pub terminals: HashMap<Terminal, usize>,
+    pub variables: HashMap<Variable, usize>,
+    /// rules the reduce entries are numbers of, the table itself only needs their clause and length
+    pub rules: Vec<syntax::Rule>,
+    pub default_reduction: Vec<Option<u32>>,
+    /// shift to state s is s + 1, reduce by rule r is -(r + 1)
+    pub action: Comb,
+    pub goto: Comb,
+    pub conflicts: Vec<Conflict>,
+    /// bytes of the table before packing
+    pub unpacked_memory: usize,
+}
+
+impl StateMachine {
+    /// Bytes used by the packed table
+    pub fn memory(&self) -> usize {
+        self.action.memory() + self.goto.memory()
+            + self.default_reduction.len() * size_of::<Option<u32>>()
+            + self.rules.len() * size_of::<(Variable, usize)>()
+            + (self.terminals.len() * size_of::<(Terminal, usize)>()) + self.variables.len() * size_of::<(Variable, usize)>()
+    }
+
+    fn decode(&self, entry: i32) -> Action {
+        match entry {
+            shift if shift > 0 => Action::Shift(shift as usize - 1),
+            reduce => {
+                let rule = (-reduce - 1) as usize;
+                match self.rules[rule].clause.symbol == syntax::END_VARIABLE {
+                    true => Action::Accept,
+                    false => Action::Reduce(rule),
+                }
+            }
+        }
+    }
+}
+
+impl<'a> IStateMachine<'a> for StateMachine {
+    type MachineDisplay = StateMachineDisplay<'a>;
+    type ItemSets = LROneItemSets;
+
+    fn display(&'a self, itemset: &'a Self::ItemSets) -> Self::MachineDisplay {
+        StateMachineDisplay::new(self, itemset)
+    }
+
+    fn from_itemset(sets: &Self::ItemSets) -> Self {
+        let machine = lr_one::StateMachine::from_itemset(sets);
+        let symbols: BTreeSet<MixedChar> = machine.states.iter()
+            .flat_map(|state| state.next.keys().copied().chain(state.reduce.keys().map(|terminal| MixedChar::Terminal(*terminal))))
+            .collect();
+        let mut terminals = HashMap::new();
+        let mut variables = HashMap::new();
+        for symbol in symbols {
+            match symbol {
+                MixedChar::Terminal(terminal) => terminals.insert(terminal, terminals.len()),
+                MixedChar::Variable(variable) => variables.insert(variable, variables.len()),
+            };
+        }
+
+        let mut default_reduction = vec![];
+        let mut action_rows = vec![];
+        let mut goto_rows = vec![];
+        let mut unpacked_memory = 0;
+        for state in &machine.states {
+            unpacked_memory += state.next.len() * size_of::<(MixedChar, usize)>();
+            unpacked_memory += state.reduce.values().map(|rule| size_of::<(Terminal, syntax::Rule)>() + sets.rules[*rule].len() * size_of::<MixedChar>()).sum::<usize>();
+
+            // the most frequent reduction becomes the default, its entries leave the row,
+            // accepting stays an explicit entry so only the end of input accepts
+            let mut counts: HashMap<usize, usize> = HashMap::new();
+            for rule in state.reduce.values().filter(|rule| sets.rules[**rule].clause.symbol != syntax::END_VARIABLE) {
+                *counts.entry(*rule).or_default() += 1;
+            }
+            let default = counts.into_iter().max_by_key(|(rule, count)| (*count, std::cmp::Reverse(*rule))).map(|(rule, _)| rule);
+            default_reduction.push(default.map(|rule| rule as u32));
+
+            let mut row = vec![];
+            let mut gotos = vec![];
+            for (symbol, next) in &state.next {
+                match symbol {
+                    MixedChar::Terminal(terminal) => row.push((terminals[terminal], *next as i32 + 1)),
+                    MixedChar::Variable(variable) => gotos.push((variables[variable], *next as i32)),
+                }
+            }
+            for (terminal, &rule) in &state.reduce {
+                if Some(rule) != default && !state.next.contains_key(&MixedChar::Terminal(*terminal)) {
+                    row.push((terminals[terminal], -(rule as i32) - 1));
+                }
+            }
+            row.sort();
+            gotos.sort();
+            action_rows.push(row);
+            goto_rows.push(gotos);
+        }
+
+        Self {
+            terminals,
+            variables,
+            rules: sets.rules.clone(),
+            default_reduction,
+            action: Comb::pack(&action_rows),
+            goto: Comb::pack(&goto_rows),
+            conflicts: machine.conflicts,
+            unpacked_memory,
+        }
+    }
+
+    fn next_action(&self, index: usize, rest: Option<Terminal>) -> Action {
+        let entry = rest.and_then(|terminal| self.terminals.get(&terminal)).and_then(|column| self.action.get(index, *column));
+        match (entry, self.default_reduction[index]) {
+            (Some(entry), _) => self.decode(entry),
+            (None, Some(rule)) => self.decode(-(rule as i32) - 1),
+            (None, None) => Action::Reject,
+        }
+    }
+
+    fn reduce_state(&self, index: usize, variable: Variable) -> usize {
+        self.variables.get(&variable)
+            .and_then(|column| self.goto.get(index, *column))
+            .map_or(0, |next| next as usize)
+    }
+
+    fn conflicts(&self) -> &[Conflict] {
+        &self.conflicts
+    }
+    fn rules(&self) -> &[syntax::Rule] {
+        &self.rules
+    }
+    fn state_count(&self) -> usize {
+        self.default_reduction.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Comb, StateMachine};
+    use crate::generate::{self, Random};
+    use crate::itemset::LROneItemSets;
+    use crate::parsing::ParsingProcess;
+    use crate::parsing_table::{lr_one, IStateMachine};
+    use crate::syntax::tests::rules;
+    use crate::syntax::{self, TerminalString, Variable};
+
+    /// Verdict of the machine with the clause and length of every reduction made on the way
+    fn reductions<'a, Machine: IStateMachine<'a>>(machine: &Machine, sentence: &TerminalString) -> (bool, Vec<(char, usize)>) {
+        let input: Vec<char> = sentence.data.iter().map(|terminal| terminal.symbol).chain([syntax::END_TERMINAL]).collect();
+        let mut reduced = vec![];
+        let accepted = ParsingProcess::new(&input).parse_with(machine, |variable, children| reduced.push((variable.symbol, children.len()))).is_ok();
+        (accepted, reduced)
+    }
+
+    #[test]
+    fn same_parses_as_the_lr_one_table() {
+        let grammar = ["E:E+T", "E:T", "T:T*F", "T:F", "F:(E)", "F:i", "F:-F"];
+        let mut itemsets = LROneItemSets::new('E');
+        for rule in grammar {
+            assert!(itemsets.add_from_string(rule));
+        }
+        itemsets.generate_next();
+        let (compressed, reference) = (StateMachine::from_itemset(&itemsets), lr_one::StateMachine::from_itemset(&itemsets));
+        assert!(compressed.memory() < compressed.unpacked_memory);
+        let samples = generate::corpus(&rules(&grammar), Variable { symbol: 'E' }, 100, &mut Random::new(11));
+        assert!(samples.iter().any(|sample| sample.expected == Some(false)));
+        for sample in samples {
+            let (accepted, reduced) = reductions(&compressed, &sample.sentence);
+            let (expected, expected_reduced) = reductions(&reference, &sample.sentence);
+            assert_eq!((accepted, Some(accepted)), (expected, sample.expected), "{}", sample.sentence);
+            // default reductions may reduce further before an error shows up
+            if accepted {
+                assert_eq!(reduced, expected_reduced, "{}", sample.sentence);
+            }
+        }
+    }
+
+    #[test]
+    fn packed_rows_do_not_collide() {
+        let mut random = Random::new(5);
+        let rows: Vec<Vec<(usize, i32)>> = (0..40)
+            .map(|_| {
+                let columns: Vec<usize> = (0..12).filter(|_| random.below(3) == 0).collect();
+                columns.into_iter().map(|column| (column, random.below(100) as i32 + 1)).collect()
+            })
+            .collect();
+        let comb = Comb::pack(&rows);
+        let entries: usize = rows.iter().map(Vec::len).sum();
+        assert_eq!(comb.check.iter().filter(|owner| **owner != Comb::FREE).count(), entries);
+        assert!(comb.check.len() < rows.len() * 12);
+        for (row, entries) in rows.iter().enumerate() {
+            for column in 0..12 {
+                let expected = entries.iter().find(|(found, _)| *found == column).map(|(_, value)| *value);
+                assert_eq!(comb.get(row, column), expected, "row {} column {}", row, column);
+            }
+        }
+    }
+}
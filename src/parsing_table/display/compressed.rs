@@ -0,0 +1,53 @@
+use std::fmt::Display;
+use crate::itemset::{Item as _, LROneItemSets};
+use crate::parsing_table::{Action, IStateMachine};
+use crate::parsing_table::compressed::StateMachine;
+
+pub struct StateMachineDisplay<'a> {
+    machine: &'a StateMachine,
+    sets: &'a LROneItemSets,
+}
+
+impl<'a> StateMachineDisplay<'a> {
+    pub fn new(machine: &'a StateMachine, sets: &'a LROneItemSets) -> Self {
+        Self { machine, sets }
+    }
+}
+
+impl Display for StateMachineDisplay<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut terminals: Vec<_> = self.machine.terminals.iter().collect();
+        terminals.sort_by_key(|(_, column)| **column);
+        let mut variables: Vec<_> = self.machine.variables.iter().collect();
+        variables.sort_by_key(|(_, column)| **column);
+        for (index, itemset) in self.sets.sets.iter().enumerate() {
+            writeln!(f, "state {}", index)?;
+            for item in itemset.items.iter().filter(|item| item.kernel()) {
+                f.write_str("    ")?;
+                item.display(&self.sets.rules).fmt(f)?;
+                f.write_str("*\n")?;
+            }
+            writeln!(f)?;
+            for (terminal, _) in &terminals {
+                match self.machine.next_action(index, Some(**terminal)) {
+                    Action::Shift(next) => writeln!(f, "    {:4}shift {}", terminal, next)?,
+                    Action::Accept => writeln!(f, "    {:4}accept", terminal)?,
+                    Action::Reduce(rule) => writeln!(f, "    {:4}reduce {}", terminal, self.machine.rules[rule])?,
+                    Action::Reject => {}
+                }
+            }
+            for (variable, _) in &variables {
+                let next = self.machine.reduce_state(index, **variable);
+                if next != 0 {
+                    writeln!(f, "    {:4}goto {}", variable, next)?;
+                }
+            }
+            if let Some(rule) = self.machine.default_reduction[index] {
+                writeln!(f, "    default reduce {}", self.sets.rules[rule as usize])?;
+            }
+        }
+        writeln!(f, "base   {:?}", self.machine.action.base)?;
+        writeln!(f, "value  {:?}", self.machine.action.value)?;
+        writeln!(f, "check  {:?}", self.machine.action.check)
+    }
+}
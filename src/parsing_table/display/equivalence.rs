@@ -0,0 +1,35 @@
+use std::fmt::Display;
+use crate::parsing_table::Action;
+use crate::parsing_table::equivalence::Behavior;
+
+pub struct BehaviorDisplay<'a> {
+    behavior: &'a Behavior,
+}
+
+impl<'a> BehaviorDisplay<'a> {
+    pub fn new(behavior: &'a Behavior) -> Self {
+        Self { behavior }
+    }
+}
+
+impl Display for BehaviorDisplay<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (index, (actions, gotos)) in self.behavior.actions.iter().zip(&self.behavior.gotos).enumerate() {
+            writeln!(f, "state {}", index)?;
+            for (terminal, action) in self.behavior.terminals.iter().zip(actions) {
+                match action {
+                    Action::Shift(next) => writeln!(f, "    {:4}shift {}", terminal, next)?,
+                    Action::Accept => writeln!(f, "    {:4}accept", terminal)?,
+                    Action::Reduce(rule) => writeln!(f, "    {:4}reduce {}", terminal, self.behavior.rules[*rule])?,
+                    Action::Reject => {}
+                }
+            }
+            for (variable, next) in self.behavior.variables.iter().zip(gotos) {
+                if let Some(next) = next {
+                    writeln!(f, "    {:4}goto {}", variable, next)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,64 @@
+use std::fmt::Display;
+use crate::itemset::{ItemSet as _, ItemSets as _, LRkItemSets};
+use crate::parsing_table::lr_k::{State, StateMachine};
+use crate::syntax::TerminalString;
+use crate::syntax;
+use crate::itemset::Item as _;
+
+pub struct StateMachineDisplay<'a> {
+    states: &'a [State],
+    sets: &'a LRkItemSets,
+}
+
+impl<'a> StateMachineDisplay<'a> {
+    pub fn new(machine: &'a StateMachine, sets: &'a LRkItemSets) -> Self {
+        Self { states: &machine.states, sets }
+    }
+}
+
+impl<'a> Display for StateMachineDisplay<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (index, (state, itemset)) in self.states.iter().zip(&self.sets.sets).enumerate(){
+            writeln!(f, "state {}", index)?;
+            for item in itemset.items.iter().filter(|item| item.kernel()) {
+                f.write_str("    ")?;
+                item.display(&self.sets.rules).fmt(f)?;
+                f.write_str("*\n")?;
+            }
+            writeln!(f)?;
+            for (requirement, next_id) in state.next.iter() {
+                use crate::syntax::MixedChar::{Terminal, Variable};
+                match requirement {
+                    Terminal(t) => writeln!(f, "    {:4}shift {}", t, next_id),
+                    Variable(v) => writeln!(f, "    {:4}goto {}", v, next_id),
+                }?;
+            }
+            if !state.shift.is_empty() {
+                let lookaheads: Vec<String> = state.shift.iter().map(|follow| TerminalString { data: follow.clone() }.to_string()).collect();
+                writeln!(f, "    shift on {}", lookaheads.join(" "))?;
+            }
+            if !state.reduce.is_empty() && !state.next.is_empty() {
+                writeln!(f)?;
+            }
+            for (follow, rule) in &state.reduce {
+                let rule = &self.sets.rules[*rule];
+                let follow_display = TerminalString { data: follow.clone() }.to_string();
+                if state.shift.contains(follow) {
+                    writeln!(f, "    shift-reduce conflict on {}", follow_display)?;
+                    writeln!(f, "        favor shift over reduce({})", rule)?;
+                    continue;
+                }
+                for (rule1, rule2) in itemset.reduce_reduce_conflict(self.sets.rules()) {
+                    writeln!(f, "    reduce-reduce between rule {}and {}", rule1, rule2)?;
+                    writeln!(f, "        Favor rule {}over rule {}", rule1, rule2)?;
+                }
+                if rule.clause.symbol == syntax::END_VARIABLE {
+                    writeln!(f, "    {:4}accept", follow_display)?;
+                    continue;
+                }
+                writeln!(f, "    {:4}reduce {}", follow_display, rule)?;
+            }
+        }
+        Ok(())
+    }
+}
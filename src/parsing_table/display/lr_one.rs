@@ -45,6 +45,7 @@ impl<'a> Display for StateMachineDisplay<'a> {
                 write!(f,"\n")?;
             }
             for (follow, rule) in &state.reduce {
+                let rule = &self.sets.rules[*rule];
                 if let Some(next_state_id) = state.next.get(&(MixedChar::from(*follow))) {
                     write!(f, "    shift-reduce conflict on {}\n", follow)?;
                     write!(f, "        favor shift({}) over reduce({})\n", next_state_id, rule)?;
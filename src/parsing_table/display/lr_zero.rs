@@ -40,7 +40,7 @@ impl<'a> Display for StateMachineDisplay<'a> {
             if state.reduce.is_some() && state.next.len() != 0 {
                 write!(f,"\n")?;
             }
-            for rule in &state.reduce {
+            for rule in state.reduce.iter().map(|rule| &self.sets.rules[*rule]) {
                 for next_state_id in state.next.values() {
                     write!(f, "    shift-reduce conflict \n")?;
                     write!(f, "        favor shift({}) over reduce({})\n", next_state_id, rule)?;
@@ -1,2 +1,5 @@
 pub mod lr_one;
 pub mod lr_zero;
+pub mod lr_k;
+pub mod compressed;
+pub mod equivalence;
@@ -0,0 +1,230 @@
+use std::collections::{BTreeSet, HashMap};
+
+use crate::itemset::LROneItemSets;
+use crate::parsing_table::{Action, Conflict, IStateMachine, lr_one};
+use crate::parsing_table::display::equivalence::BehaviorDisplay;
+use crate::syntax::{self, Rule, Terminal, Variable};
+
+/// What a machine does in every state, the action on each terminal and the goto on each variable.
+/// Two states with the same rows, up to the states they lead to, can not be told apart by any input
+#[derive(Clone)]
+pub struct Behavior {
+    pub terminals: Vec<Terminal>,
+    pub variables: Vec<Variable>,
+    pub actions: Vec<Vec<Action>>,
+    pub gotos: Vec<Vec<Option<usize>>>,
+    pub rules: Vec<Rule>,
+    pub conflicts: Vec<Conflict>,
+}
+
+/// Row of a state with every target replaced through `class`
+type Signature = (Vec<Action>, Vec<Option<usize>>);
+
+impl Behavior {
+    /// Reads every row of the machine, the alphabet is taken from the rules
+    pub fn of<Machine>(machine: &Machine, rules: &[Rule]) -> Self
+        where Machine: for<'a> IStateMachine<'a> {
+        let mut terminals: BTreeSet<Terminal> = rules.iter()
+            .flat_map(|rule| rule.output.data.iter().filter_map(|symbol| Terminal::try_from(symbol).ok()))
+            .collect();
+        terminals.insert(Terminal::end());
+        let variables: BTreeSet<Variable> = rules.iter()
+            .map(|rule| rule.clause)
+            .filter(|variable| variable.symbol != syntax::END_VARIABLE)
+            .collect();
+        let (terminals, variables): (Vec<Terminal>, Vec<Variable>) = (terminals.into_iter().collect(), variables.into_iter().collect());
+        let states = 0..machine.state_count();
+        let actions = states.clone()
+            .map(|state| terminals.iter().map(|terminal| machine.next_action(state, Some(*terminal))).collect())
+            .collect();
+        // state 0 is never a goto target, reduce_state gives 0 for a missing entry
+        let gotos = states
+            .map(|state| variables.iter().map(|variable| Some(machine.reduce_state(state, *variable)).filter(|next| *next != 0)).collect())
+            .collect();
+        Self { terminals, variables, actions, gotos, rules: machine.rules().to_vec(), conflicts: machine.conflicts().to_vec() }
+    }
+
+    pub fn len(&self) -> usize {
+        self.actions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.actions.is_empty()
+    }
+
+    fn signature(&self, state: usize, class: &[usize]) -> Signature {
+        let actions = self.actions[state].iter().map(|action| match action {
+            Action::Shift(next) => Action::Shift(class[*next]),
+            action => *action,
+        }).collect();
+        let gotos = self.gotos[state].iter().map(|next| next.map(|next| class[next])).collect();
+        (actions, gotos)
+    }
+
+    /// Class of every state once states with the same behavior are merged, numbered by their first state
+    /// so the start state stays 0. Classes are split until every target agrees, as in Moore's algorithm
+    pub fn equivalent_states(&self) -> Vec<usize> {
+        let mut class = vec![0; self.len()];
+        let mut count = 1;
+        loop {
+            let mut number: HashMap<(usize, Signature), usize> = HashMap::new();
+            let next: Vec<usize> = (0..self.len())
+                .map(|state| {
+                    let id = number.len();
+                    *number.entry((class[state], self.signature(state, &class))).or_insert(id)
+                })
+                .collect();
+            class = next;
+            if number.len() == count {
+                return class;
+            }
+            count = number.len();
+        }
+    }
+
+    /// The machine with every class of equivalent states merged into one state
+    pub fn minimized(&self) -> Self {
+        let class = self.equivalent_states();
+        let mut representative: Vec<usize> = vec![];
+        for (state, id) in class.iter().enumerate() {
+            if *id == representative.len() {
+                representative.push(state);
+            }
+        }
+        let (actions, gotos) = representative.iter().map(|state| self.signature(*state, &class)).unzip();
+        Self {
+            terminals: self.terminals.clone(),
+            variables: self.variables.clone(),
+            actions,
+            gotos,
+            rules: self.rules.clone(),
+            conflicts: self.conflicts.clone(),
+        }
+    }
+
+    /// State of `other` matching every state of this machine, `None` when the machines differ
+    /// by more than the numbering of their states
+    pub fn isomorphism(&self, other: &Self) -> Option<Vec<usize>> {
+        let terminal_column: HashMap<Terminal, usize> = other.terminals.iter().enumerate().map(|(column, terminal)| (*terminal, column)).collect();
+        let variable_column: HashMap<Variable, usize> = other.variables.iter().enumerate().map(|(column, variable)| (*variable, column)).collect();
+        if self.len() != other.len() || self.terminals.len() != other.terminals.len() || self.variables.len() != other.variables.len() {
+            return None;
+        }
+        let mut forward: Vec<Option<usize>> = vec![None; self.len()];
+        let mut backward: Vec<Option<usize>> = vec![None; other.len()];
+        let mut pending = vec![(0, 0)];
+        while let Some((left, right)) = pending.pop() {
+            match (forward[left], backward[right]) {
+                (Some(matched), _) if matched == right => continue,
+                (None, None) => {
+                    forward[left] = Some(right);
+                    backward[right] = Some(left);
+                }
+                _ => return None,
+            }
+            for (terminal, action) in self.terminals.iter().zip(&self.actions[left]) {
+                let other_action = other.actions[right][*terminal_column.get(terminal)?];
+                match (action, other_action) {
+                    (Action::Shift(next), Action::Shift(other_next)) => pending.push((*next, other_next)),
+                    (action, other_action) if *action == other_action => {}
+                    _ => return None,
+                }
+            }
+            for (variable, next) in self.variables.iter().zip(&self.gotos[left]) {
+                match (next, other.gotos[right][*variable_column.get(variable)?]) {
+                    (Some(next), Some(other_next)) => pending.push((*next, other_next)),
+                    (None, None) => {}
+                    _ => return None,
+                }
+            }
+        }
+        forward.into_iter().collect()
+    }
+}
+
+impl<'a> IStateMachine<'a> for Behavior {
+    type MachineDisplay = BehaviorDisplay<'a>;
+    type ItemSets = LROneItemSets;
+
+    fn display(&'a self, _itemset: &'a Self::ItemSets) -> Self::MachineDisplay {
+        BehaviorDisplay::new(self)
+    }
+
+    /// Minimized lr(1) machine of the item sets
+    fn from_itemset(sets: &Self::ItemSets) -> Self {
+        Self::of(&lr_one::StateMachine::from_itemset(sets), &sets.rules).minimized()
+    }
+
+    fn next_action(&self, index: usize, rest: Option<Terminal>) -> Action {
+        let column = rest.and_then(|terminal| self.terminals.iter().position(|known| *known == terminal));
+        column.map_or(Action::Reject, |column| self.actions[index][column])
+    }
+
+    fn reduce_state(&self, index: usize, variable: Variable) -> usize {
+        let column = self.variables.iter().position(|known| *known == variable);
+        column.and_then(|column| self.gotos[index][column]).unwrap_or(0)
+    }
+
+    fn conflicts(&self) -> &[Conflict] {
+        &self.conflicts
+    }
+
+    fn rules(&self) -> &[Rule] {
+        &self.rules
+    }
+
+    fn state_count(&self) -> usize {
+        self.len()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::Behavior;
+    use crate::itemset::LROneItemSets;
+    use crate::parsing_table::{IStateMachine, lr_one};
+
+    fn itemsets(rules: &[&str]) -> LROneItemSets {
+        let mut itemsets = LROneItemSets::new('E');
+        for rule in rules {
+            assert!(itemsets.add_from_string(rule));
+        }
+        itemsets.generate_next();
+        itemsets
+    }
+
+    fn behavior(itemsets: &LROneItemSets) -> Behavior {
+        Behavior::of(&lr_one::StateMachine::from_itemset(itemsets), &itemsets.rules)
+    }
+
+    /// The item sets with state `state` moved to `renumbered[state]`
+    fn renumber(itemsets: &LROneItemSets, renumbered: &[usize]) -> LROneItemSets {
+        let mut sets = itemsets.sets.clone();
+        let mut ordering_map = itemsets.ordering_map.clone();
+        for (state, next) in renumbered.iter().enumerate() {
+            sets[*next] = itemsets.sets[state].clone();
+            ordering_map[*next] = itemsets.ordering_map[state].iter().map(|(symbol, target)| (*symbol, renumbered[*target])).collect();
+        }
+        LROneItemSets { sets, rules: itemsets.rules.clone(), ordering_map }
+    }
+
+    #[test]
+    fn renumbered_states_are_isomorphic() {
+        let itemsets = itemsets(&["E:E+T", "E:T", "T:T*F", "T:F", "F:(E)", "F:i"]);
+        let count = itemsets.sets.len();
+        // the start state stays first, every other state is numbered backwards
+        let renumbered: Vec<usize> = (0..count).map(|state| (count - state) % count).collect();
+        let (machine, other) = (behavior(&itemsets), behavior(&renumber(&itemsets, &renumbered)));
+        assert_eq!(machine.isomorphism(&other), Some(renumbered));
+        assert!(machine.minimized().isomorphism(&other.minimized()).is_some());
+    }
+
+    #[test]
+    fn different_grammars_are_not_isomorphic() {
+        let machine = behavior(&itemsets(&["E:E+T", "E:T", "T:i"]));
+        let other = behavior(&itemsets(&["E:T+E", "E:T", "T:i"]));
+        assert_eq!(machine.len(), other.len());
+        assert_eq!(machine.isomorphism(&other), None);
+    }
+}
@@ -0,0 +1,152 @@
+use std::collections::{BTreeSet, HashMap};
+use crate::first_follow::FirstK;
+use crate::parsing_table::{Action, Conflict};
+use crate::parsing_table::display::lr_k::StateMachineDisplay;
+use crate::syntax;
+use crate::syntax::{MixedChar, Rule, Terminal, Variable};
+
+#[derive(Clone)]
+pub struct State{
+    pub next: HashMap<MixedChar, usize>,
+    pub shift: BTreeSet<Vec<Terminal>>,
+    /// index of the rule reduced on every lookahead
+    pub reduce: HashMap<Vec<Terminal>, usize>,
+    pub conflicting: HashMap<Vec<Terminal>, Vec<usize>>,
+}
+
+impl State {
+    fn new() -> Self {
+        Self {
+            next: HashMap::new(),
+            shift: BTreeSet::new(),
+            reduce: HashMap::new(),
+            conflicting: HashMap::new(),
+        }
+    }
+    fn check_terminal(&self, terminal: &Terminal) -> Option<usize>{
+        self.next.get(&MixedChar::Terminal(*terminal)).copied()
+    }
+    fn check_variable(&self, variable: &Variable) -> Option<usize>{
+        self.next.get(&MixedChar::Variable(*variable)).copied()
+    }
+}
+
+/// LR(k) table, an action is picked by the next k terminals of the input
+pub struct StateMachine{
+    pub k: usize,
+    pub states: Vec<State>,
+    pub rules: Vec<Rule>,
+    pub conflicts: Vec<Conflict>,
+}
+
+impl<'a> super::IStateMachine<'a> for StateMachine {
+    type MachineDisplay = StateMachineDisplay<'a>;
+    type ItemSets = crate::itemset::LRkItemSets;
+    fn display(&'a self, itemset: &'a Self::ItemSets) -> StateMachineDisplay<'a> {
+        StateMachineDisplay::new(self, itemset)
+    }
+
+    fn from_itemset(sets: &Self::ItemSets) -> Self {
+        let first = FirstK::from_rule(&sets.rules, sets.k);
+        let mut machine = Self{
+            k: sets.k,
+            states: vec![State::new(); sets.sets.len()],
+            rules: sets.rules.clone(),
+            conflicts: vec![],
+        };
+        let rule_number = super::rule_numbers(&sets.rules);
+        for (current_state, next_states) in sets.ordering_map.iter().enumerate() {
+            next_states.iter().for_each(|(k, v)| {
+                machine.states[current_state].next.insert(*k, *v);
+            })
+        }
+
+        for (id, set) in sets.sets.iter().enumerate() {
+            machine.states[id].shift = set.shifts(&sets.rules, &first);
+            for (rule, follow) in set.reduce(&sets.rules) {
+                let terminal = follow.first().copied().unwrap_or(Terminal::end());
+                if let Some(current_rule) = machine.states[id].reduce.get(follow){
+                    let conflict = Conflict::ReduceReduce { state: id, terminal: Some(terminal), kept: sets.rules[*current_rule].clone(), dropped: rule.clone() };
+                    machine.conflicts.push(conflict);
+                    machine.states[id].conflicting.entry(follow.to_vec()).or_default().push(rule_number[rule]);
+                    continue;
+                }
+                if machine.states[id].shift.contains(follow) {
+                    machine.conflicts.push(Conflict::ShiftReduce { state: id, terminal, rule: rule.clone() });
+                }
+                machine.states[id].reduce.insert(follow.to_vec(), rule_number[rule]);
+            }
+        }
+        machine
+    }
+
+    fn next_action(&self, index: usize, rest: Option<Terminal>) -> Action {
+        self.lookahead_action(index, rest.as_slice())
+    }
+
+    fn lookahead(&self) -> usize {
+        self.k
+    }
+
+    fn lookahead_action(&self, index: usize, rest: &[Terminal]) -> Action {
+        let cur_state = &self.states[index];
+        let current = &rest[..rest.len().min(self.k)];
+        if let (true, Some(terminal)) = (cur_state.shift.contains(current), current.first()) {
+            if let Some(next) = cur_state.check_terminal(terminal) {
+                return Action::Shift(next);
+            }
+        }
+        if let Some(rule) = cur_state.reduce.get(current) {
+            if self.rules[*rule].clause.symbol == syntax::END_VARIABLE {
+                return Action::Accept;
+            }
+            return Action::Reduce(*rule);
+        }
+        Action::Reject
+    }
+
+    fn reduce_state(&self, index: usize, variable: Variable) -> usize {
+        self.states[index].check_variable(&variable).unwrap_or(0)
+    }
+    fn conflicts(&self) -> &[Conflict] {
+        &self.conflicts
+    }
+    fn rules(&self) -> &[Rule] {
+        &self.rules
+    }
+    fn state_count(&self) -> usize {
+        self.states.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parsing::tests::{lr_k, lr_one, process};
+    use crate::parsing_table::IStateMachine;
+
+    // after b both A and B can be reduced on a, only the terminal after the a tells them apart
+    const TWO_LOOKAHEAD: [&str; 4] = ["E:Aac", "E:Bad", "A:b", "B:b"];
+
+    #[test]
+    fn two_terminals_resolve_an_lr_one_conflict() {
+        assert!(!lr_one(&TWO_LOOKAHEAD).conflicts().is_empty());
+        let machine = lr_k(&TWO_LOOKAHEAD, 2);
+        assert!(machine.conflicts().is_empty());
+        assert_eq!(machine.lookahead(), 2);
+        let tree = process("bad").parse(&machine).unwrap();
+        assert_eq!(tree.to_string().lines().nth(1).unwrap().trim(), "B 1:1..1:2");
+        let tree = process("bac").parse(&machine).unwrap();
+        assert_eq!(tree.to_string().lines().nth(1).unwrap().trim(), "A 1:1..1:2");
+        assert!(process("bae").parse(&machine).is_err());
+        assert!(process("ba").parse(&machine).is_err());
+    }
+
+    #[test]
+    fn one_terminal_is_the_lr_one_table() {
+        let grammar = ["E:E+T", "E:T", "T:a"];
+        let (machine, reference) = (lr_k(&grammar, 1), lr_one(&grammar));
+        assert_eq!(machine.state_count(), reference.state_count());
+        assert!(process("a+a").parse(&machine).is_ok());
+        assert!(process("a+").parse(&machine).is_err());
+    }
+}
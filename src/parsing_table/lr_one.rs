@@ -1,6 +1,6 @@
 use std::collections::HashMap;
 // use crate::itemset::LROneItemSets;
-use crate::parsing_table::{Action};
+use crate::parsing_table::{Action, Conflict};
 use crate::parsing_table::display::lr_one::StateMachineDisplay;
 use crate::syntax;
 use crate::syntax::{MixedChar, Rule, Terminal, Variable};
@@ -8,14 +8,17 @@ use crate::syntax::{MixedChar, Rule, Terminal, Variable};
 #[derive(Clone)]
 pub struct State{
     pub next: HashMap<MixedChar, usize>,
-    pub reduce: HashMap<Terminal, Rule>
+    /// index of the rule reduced on every terminal
+    pub reduce: HashMap<Terminal, usize>,
+    pub conflicting: HashMap<Terminal, Vec<usize>>,
 }
 
 impl State {
     fn new() -> Self {
         Self {
             next: HashMap::new(),
-            reduce: HashMap::new()
+            reduce: HashMap::new(),
+            conflicting: HashMap::new(),
         }
     }
     fn check_terminal(&self, terminal: &Terminal) -> Option<usize>{
@@ -28,6 +31,8 @@ impl State {
 
 pub struct StateMachine{
     pub states: Vec<State>,
+    pub rules: Vec<Rule>,
+    pub conflicts: Vec<Conflict>,
 }
 
 impl<'a> super::IStateMachine<'a> for StateMachine {
@@ -39,8 +44,11 @@ impl<'a> super::IStateMachine<'a> for StateMachine {
 
     fn from_itemset(sets: &Self::ItemSets) -> Self {
         let mut machine = Self{
-            states: vec![State::new(); sets.sets.len()]
+            states: vec![State::new(); sets.sets.len()],
+            rules: sets.rules.clone(),
+            conflicts: vec![],
         };
+        let rule_number = super::rule_numbers(&sets.rules);
         for (current_state, next_states) in sets.ordering_map.iter().enumerate() {
             next_states.iter().for_each(|(k, v)| {
                 machine.states[current_state].next.insert(*k, *v);
@@ -49,10 +57,16 @@ impl<'a> super::IStateMachine<'a> for StateMachine {
 
         for (id, set) in sets.sets.iter().enumerate() {
             for (rule, follow) in set.reduce(&sets.rules) {
-                if let Some(_current_rule) = &machine.states[id].reduce.get(&follow){
+                if let Some(current_rule) = machine.states[id].reduce.get(&follow){
+                    let conflict = Conflict::ReduceReduce { state: id, terminal: Some(follow), kept: sets.rules[*current_rule].clone(), dropped: rule.clone() };
+                    machine.conflicts.push(conflict);
+                    machine.states[id].conflicting.entry(follow).or_default().push(rule_number[rule]);
                     continue;
                 }
-                machine.states[id].reduce.insert(follow, rule.clone());
+                if machine.states[id].check_terminal(&follow).is_some() {
+                    machine.conflicts.push(Conflict::ShiftReduce { state: id, terminal: follow, rule: rule.clone() });
+                }
+                machine.states[id].reduce.insert(follow, rule_number[rule]);
             }
         }
         machine
@@ -64,13 +78,11 @@ impl<'a> super::IStateMachine<'a> for StateMachine {
         if let Some(next) = cur_state.check_terminal(&current) {
             return Action::Shift(next);
         }
-        if let Some(rule) = &cur_state.reduce.get(&current){
-            let clause = rule.clause;
-            let reduce_state = rule.len();
-            if clause.symbol == syntax::END_VARIABLE {
+        if let Some(rule) = cur_state.reduce.get(&current){
+            if self.rules[*rule].clause.symbol == syntax::END_VARIABLE {
                 return Action::Accept;
             }
-            return Action::Reduce(clause, reduce_state)
+            return Action::Reduce(*rule)
         }
 
         return Action::Reject;
@@ -78,5 +90,28 @@ impl<'a> super::IStateMachine<'a> for StateMachine {
     fn reduce_state(&self, index: usize, variable: Variable) -> usize {
         self.states[index].check_variable(&variable).unwrap_or(0)
     }
+    fn conflicts(&self) -> &[Conflict] {
+        &self.conflicts
+    }
+    fn rules(&self) -> &[Rule] {
+        &self.rules
+    }
+    fn state_count(&self) -> usize {
+        self.states.len()
+    }
+
+    fn actions(&self, index: usize, rest: Option<Terminal>) -> Vec<Action> {
+        let cur_state = &self.states[index];
+        let current = rest.unwrap_or(Terminal::epsilon());
+        let mut actions: Vec<Action> = cur_state.check_terminal(&current).map(Action::Shift).into_iter().collect();
+        let conflicting = cur_state.conflicting.get(&current).into_iter().flatten();
+        for rule in cur_state.reduce.get(&current).into_iter().chain(conflicting) {
+            match self.rules[*rule].clause.symbol == syntax::END_VARIABLE {
+                true => actions.push(Action::Accept),
+                false => actions.push(Action::Reduce(*rule)),
+            }
+        }
+        actions
+    }
 
 }
\ No newline at end of file
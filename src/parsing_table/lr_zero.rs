@@ -1,19 +1,22 @@
 use std::collections::HashMap;
-use crate::parsing_table::{Action, display};
+use crate::parsing_table::{Action, Conflict, display};
 use crate::syntax;
 use crate::syntax::{MixedChar, Rule, Terminal, Variable};
 
 #[derive(Clone)]
 pub struct State{
     pub next: HashMap<MixedChar, usize>,
-    pub reduce: Option<Rule>
+    /// index of the rule reduced on any terminal
+    pub reduce: Option<usize>,
+    pub conflicting: Vec<usize>,
 }
 
 impl State {
     fn new() -> Self {
         Self {
             next: HashMap::new(),
-            reduce: None
+            reduce: None,
+            conflicting: vec![],
         }
     }
     fn check_terminal(&self, terminal: &Terminal) -> Option<usize>{
@@ -26,6 +29,8 @@ impl State {
 
 pub struct StateMachine{
     pub states: Vec<State>,
+    pub rules: Vec<Rule>,
+    pub conflicts: Vec<Conflict>,
 }
 
 impl<'a> super::IStateMachine<'a> for StateMachine {
@@ -40,27 +45,51 @@ impl<'a> super::IStateMachine<'a> for StateMachine {
         self.states[index].check_variable(&variable).unwrap_or(0)
     }
 
+    fn conflicts(&self) -> &[Conflict] {
+        &self.conflicts
+    }
+    fn rules(&self) -> &[Rule] {
+        &self.rules
+    }
+    fn state_count(&self) -> usize {
+        self.states.len()
+    }
+
+    fn actions(&self, index: usize, rest: Option<Terminal>) -> Vec<Action> {
+        let cur_state = &self.states[index];
+        let current = rest.unwrap_or(Terminal::epsilon());
+        let mut actions: Vec<Action> = cur_state.check_terminal(&current).map(Action::Shift).into_iter().collect();
+        for rule in cur_state.reduce.iter().chain(&cur_state.conflicting) {
+            match self.rules[*rule].clause.symbol == syntax::END_VARIABLE {
+                true => actions.push(Action::Accept),
+                false => actions.push(Action::Reduce(*rule)),
+            }
+        }
+        actions
+    }
+
     fn next_action(&self, index: usize, rest: Option<Terminal>) -> Action {
         let cur_state = &self.states[index];
         let current = rest.unwrap_or(Terminal::epsilon());
         if let Some(next) = cur_state.check_terminal(&current) {
             return Action::Shift(next);
         }
-        if let Some(rule) = &cur_state.reduce{
-            let clause = rule.clause;
-            let reduce_state = rule.len();
-            if clause.symbol == syntax::END_VARIABLE {
+        if let Some(rule) = cur_state.reduce{
+            if self.rules[rule].clause.symbol == syntax::END_VARIABLE {
                 return Action::Accept;
             }
-            return Action::Reduce(clause, reduce_state)
+            return Action::Reduce(rule)
         }
 
         return Action::Reject;
     }
     fn from_itemset(sets: &Self::ItemSets) -> Self {
         let mut machine = Self{
-            states: vec![State::new(); sets.sets.len()]
+            states: vec![State::new(); sets.sets.len()],
+            rules: sets.rules.clone(),
+            conflicts: vec![],
         };
+        let rule_number = super::rule_numbers(&sets.rules);
         for (current_state, next_states) in sets.ordering_map.iter().enumerate() {
             next_states.iter().for_each(|(k, v)| {
                 machine.states[current_state].next.insert(*k, *v);
@@ -69,11 +98,20 @@ impl<'a> super::IStateMachine<'a> for StateMachine {
 
         for (id, set) in sets.sets.iter().enumerate() {
             for rule in set.reduce(&sets.rules) {
-                if let Some(current_rule) = &machine.states[id].reduce{
+                if let Some(current_rule) = machine.states[id].reduce{
+                    let current_rule = &sets.rules[current_rule];
                     println!("Found reduce-reduce conflict in state {} between rule {} and {}", id, current_rule, &rule);
+                    let conflict = Conflict::ReduceReduce { state: id, terminal: None, kept: current_rule.clone(), dropped: rule.clone() };
+                    machine.conflicts.push(conflict);
+                    machine.states[id].conflicting.push(rule_number[rule]);
                     continue;
                 }
-                machine.states[id].reduce = Some(rule.clone());
+                for symbol in machine.states[id].next.keys() {
+                    if let MixedChar::Terminal(terminal) = symbol {
+                        machine.conflicts.push(Conflict::ShiftReduce { state: id, terminal: *terminal, rule: rule.clone() });
+                    }
+                }
+                machine.states[id].reduce = Some(rule_number[rule]);
             }
         }
         machine
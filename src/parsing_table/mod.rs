@@ -1,17 +1,35 @@
+use std::collections::HashMap;
 use std::fmt::Display;
 use crate::itemset::ItemSets;
 
-use crate::syntax::{Terminal, Variable};
+use crate::syntax::{Rule, Terminal, Variable};
 
 mod display;
 pub mod lr_one;
 pub mod lr_zero;
+pub mod lr_k;
+pub mod compressed;
+pub mod equivalence;
 
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
 pub enum Action{
     Accept,
     Reject,
     Shift(usize),
-    Reduce(Variable, usize),
+    /// reduce by the rule with this index in the rules of the machine
+    Reduce(usize),
+}
+
+/// Table entry which had to be dropped while building the machine
+#[derive(Clone, Debug)]
+pub enum Conflict {
+    ShiftReduce { state: usize, terminal: Terminal, rule: Rule },
+    ReduceReduce { state: usize, terminal: Option<Terminal>, kept: Rule, dropped: Rule },
+}
+
+/// Index of every rule, a rule written twice gets its first index
+fn rule_numbers(rules: &[Rule]) -> HashMap<&Rule, usize> {
+    rules.iter().enumerate().rev().map(|(id, rule)| (rule, id)).collect()
 }
 
 pub trait IStateMachine<'a> {
@@ -21,4 +39,23 @@ pub trait IStateMachine<'a> {
     fn from_itemset(sets: &Self::ItemSets) -> Self;
     fn next_action(&self, index: usize, rest: Option<Terminal>) -> Action;
     fn reduce_state(&self, index: usize, variable: Variable) -> usize;
+    fn conflicts(&self) -> &[Conflict];
+    /// Rules the indices of the reduce actions point into
+    fn rules(&self) -> &[Rule];
+    fn state_count(&self) -> usize;
+    /// Number of terminals an action looks at
+    fn lookahead(&self) -> usize {
+        1
+    }
+    /// Action picked from the start of the remaining input, for machines looking at more than one terminal
+    fn lookahead_action(&self, index: usize, rest: &[Terminal]) -> Action {
+        self.next_action(index, rest.first().copied())
+    }
+    /// Every action of the state, including the ones dropped by conflicts
+    fn actions(&self, index: usize, rest: Option<Terminal>) -> Vec<Action> {
+        match self.next_action(index, rest) {
+            Action::Reject => vec![],
+            action => vec![action],
+        }
+    }
 }
\ No newline at end of file
@@ -45,7 +45,7 @@ impl MixedChar {
 
 }
 
-#[derive(Clone)]
+#[derive(Hash, PartialEq, Eq, Clone, PartialOrd, Ord)]
 pub struct MixedString{
     pub data: Vec<MixedChar>
 }
@@ -72,7 +72,7 @@ impl MixedString {
 }
 
 
-#[derive(Clone)]
+#[derive(Hash, PartialEq, Eq, Clone, PartialOrd, Ord)]
 pub struct TerminalString{
     pub data: Vec<Terminal>
 }
@@ -93,6 +93,13 @@ impl TerminalString {
     pub fn get(&self, index: usize) -> Option<Terminal> {
         self.data.get(index).copied()
     }
+    /// The input without the end marker appended for the LR machines
+    pub fn without_end(&self) -> &[Terminal] {
+        match self.data.split_last() {
+            Some((last, rest)) if *last == Terminal::end() => rest,
+            _ => &self.data,
+        }
+    }
 }
 
 impl<I: SliceIndex<[Terminal]>> Index<I> for TerminalString {
@@ -103,7 +110,7 @@ impl<I: SliceIndex<[Terminal]>> Index<I> for TerminalString {
 }
 
 
-#[derive(Clone)]
+#[derive(Hash, PartialEq, Eq, Clone, PartialOrd, Ord)]
 pub struct Rule{
     pub clause: Variable,
     pub output: MixedString
@@ -136,3 +143,17 @@ impl Rule {
     }
 }
 
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::{Rule, TerminalString};
+
+    /// Rules written as in the grammar file
+    pub fn rules(rules: &[&str]) -> Vec<Rule> {
+        rules.iter().map(|rule| Rule::try_from(*rule).unwrap()).collect()
+    }
+
+    pub fn sentence(text: &str) -> TerminalString {
+        text.chars().collect::<Vec<char>>().as_slice().into()
+    }
+}
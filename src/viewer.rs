@@ -0,0 +1,406 @@
+use std::collections::BTreeSet;
+use std::fmt::Write;
+
+use getch_rs::Key;
+use prettytable::{Cell, Row, Table};
+
+use crate::diagnostic::Style;
+use crate::itemset::{Item, ItemSet, ItemSets};
+use crate::parsing::{trace, ParsingProcess};
+use crate::parsing_table::{Action, IStateMachine};
+use crate::syntax::{self, MixedChar, Rule, Terminal, Variable};
+
+/// Rows of the trace shown above the state panes, the current step last
+const TRACE_ROWS: usize = 12;
+
+/// Text of every item of every state, kernel items marked with `*`
+pub fn state_items<'a, Sets: ItemSets<'a>>(sets: &'a Sets) -> Vec<Vec<String>> {
+    sets.item_sets().iter()
+        .map(|set| set.items()
+            .map(|item| match item.kernel() {
+                true => format!("{}*", item.display(sets.rules())),
+                false => item.display(sets.rules()).to_string(),
+            })
+            .collect())
+        .collect()
+}
+
+/// Parser of an edited input line, or the message why it can not be read
+pub type Reader<'machine> = Box<dyn Fn(&str) -> Result<ParsingProcess, String> + 'machine>;
+
+/// Line typed at the bottom of the screen, finished with enter
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Prompt {
+    Jump,
+    Breakpoint,
+    Edit,
+}
+
+/// Terminal ui stepping through a parse, the trace on top and the items and the action and goto row
+/// of the current state below it
+pub struct Viewer<'machine, Machine> {
+    machine: &'machine Machine,
+    items: Vec<Vec<String>>,
+    read: Reader<'machine>,
+    input: String,
+    /// every step computed so far, steps are only computed when they are first shown
+    history: Vec<ParsingProcess>,
+    current: usize,
+    state_breakpoints: BTreeSet<usize>,
+    rule_breakpoints: BTreeSet<usize>,
+    prompt: Option<(Prompt, String)>,
+    message: String,
+}
+
+impl<'machine, Machine> Viewer<'machine, Machine>
+    where Machine: for<'a> IStateMachine<'a> {
+    pub fn new(machine: &'machine Machine, items: Vec<Vec<String>>, input: String, parser: ParsingProcess,
+               read: Reader<'machine>) -> Self {
+        Self {
+            machine,
+            items,
+            read,
+            input,
+            history: vec![parser],
+            current: 0,
+            state_breakpoints: BTreeSet::new(),
+            rule_breakpoints: BTreeSet::new(),
+            prompt: None,
+            message: String::new(),
+        }
+    }
+
+    /// Moves to the next step, false when the parse has ended
+    fn forward(&mut self) -> bool {
+        if self.current + 1 == self.history.len() {
+            let Some(next) = self.history[self.current].get_next(self.machine) else {
+                return false;
+            };
+            self.history.push(next);
+        }
+        self.current += 1;
+        true
+    }
+
+    /// The step is in a state with a breakpoint or reduces a rule with one
+    fn breaks(&self, step: usize) -> bool {
+        let step = self.history[step].step(self.machine);
+        self.state_breakpoints.contains(&step.state)
+            || matches!(step.action, Action::Reduce(rule) if self.rule_breakpoints.contains(&rule))
+    }
+
+    /// Runs to the next step stopping at a breakpoint, or to the end of the parse
+    fn resume(&mut self) {
+        while self.forward() {
+            if self.breaks(self.current) {
+                self.message = format!("breakpoint at step {}", self.current);
+                return;
+            }
+        }
+        self.message = format!("the parse ended at step {}", self.current);
+    }
+
+    fn jump(&mut self, text: &str) {
+        let Ok(target) = text.trim().parse::<usize>() else {
+            self.message = format!("{:?} is not a step", text);
+            return;
+        };
+        if target < self.current {
+            self.current = target;
+            return;
+        }
+        while self.current < target && self.forward() {}
+        if self.current < target {
+            self.message = format!("the parse ended at step {}", self.current);
+        }
+    }
+
+    /// A number is a state, `A:xB` a rule as written in the grammar file. Adds the breakpoint or removes it
+    /// when it is already set, an empty line removes every breakpoint
+    fn toggle_breakpoint(&mut self, text: &str) {
+        let text = text.trim();
+        if text.is_empty() {
+            self.state_breakpoints.clear();
+            self.rule_breakpoints.clear();
+            self.message = "every breakpoint removed".to_string();
+            return;
+        }
+        let (breakpoints, id) = match (text.parse::<usize>(), Rule::try_from(text)) {
+            (Ok(state), _) if state < self.machine.state_count() => (&mut self.state_breakpoints, state),
+            (Ok(state), _) => {
+                self.message = format!("there is no state {}", state);
+                return;
+            }
+            (Err(_), Ok(rule)) => match self.machine.rules().iter().position(|known| *known == rule) {
+                Some(id) => (&mut self.rule_breakpoints, id),
+                None => {
+                    self.message = format!("there is no rule {}", trace::rule_text(&rule));
+                    return;
+                }
+            },
+            (Err(_), Err(_)) => {
+                self.message = format!("{:?} is neither a state nor a rule like A:xB", text);
+                return;
+            }
+        };
+        match breakpoints.insert(id) {
+            true => self.message = format!("breakpoint set on {}", text),
+            false => {
+                breakpoints.remove(&id);
+                self.message = format!("breakpoint removed from {}", text);
+            }
+        }
+    }
+
+    /// Parses the new input from the start, the old parse is kept when the line can not be read
+    fn edit(&mut self, text: &str) {
+        match (self.read)(text) {
+            Ok(parser) => {
+                self.input = text.to_string();
+                self.history = vec![parser];
+                self.current = 0;
+                self.message = "input replaced".to_string();
+            }
+            Err(message) => self.message = message,
+        }
+    }
+
+    /// Acts on a key, false when the viewer should close
+    pub fn handle(&mut self, key: Key) -> bool {
+        if let Some((prompt, mut line)) = self.prompt.take() {
+            match key {
+                Key::Char('\r') => match prompt {
+                    Prompt::Jump => self.jump(&line),
+                    Prompt::Breakpoint => self.toggle_breakpoint(&line),
+                    Prompt::Edit => self.edit(&line),
+                },
+                Key::Esc => {}
+                Key::Backspace | Key::Delete => {
+                    line.pop();
+                    self.prompt = Some((prompt, line));
+                }
+                Key::Char(symbol) => {
+                    line.push(symbol);
+                    self.prompt = Some((prompt, line));
+                }
+                _ => self.prompt = Some((prompt, line)),
+            }
+            return true;
+        }
+        self.message.clear();
+        match key {
+            Key::Right | Key::Char(' ') if !self.forward() => self.message = "the parse has ended".to_string(),
+            Key::Left => self.current = self.current.saturating_sub(1),
+            Key::Up => self.current = 0,
+            Key::Char('c') => self.resume(),
+            Key::Char('g') => self.prompt = Some((Prompt::Jump, String::new())),
+            Key::Char('b') => self.prompt = Some((Prompt::Breakpoint, String::new())),
+            Key::Char('e') => self.prompt = Some((Prompt::Edit, self.input.clone())),
+            Key::Down | Key::Esc | Key::Char('q') => return false,
+            _ => {}
+        }
+        true
+    }
+
+    fn trace_pane(&self, style: Style) -> Table {
+        let mut table = Table::new();
+        table.set_titles(Row::new(["Step", "State", "Stack", "Output", "Input", "Action"].iter().map(|title| Cell::new(title)).collect()));
+        for step in (self.current + 1).saturating_sub(TRACE_ROWS)..=self.current {
+            let style = match step == self.current {
+                true => style,
+                false => Style::Plain,
+            };
+            let mut row = self.history[step].display(self.machine).get_row(step, style);
+            if self.breaks(step) {
+                row.set_cell(Cell::new(&format!("{} *", step)), 0).unwrap();
+            }
+            table.add_row(row);
+        }
+        table
+    }
+
+    fn action_text(&self, action: Action) -> String {
+        match action {
+            Action::Accept => "accept".to_string(),
+            Action::Reject => "reject".to_string(),
+            Action::Shift(next) => format!("shift {}", next),
+            Action::Reduce(rule) => format!("reduce {}", trace::rule_text(&self.machine.rules()[rule])),
+        }
+    }
+
+    /// Action of the state on every terminal and goto on every variable, with the action the input calls for first
+    fn action_pane(&self, state: usize) -> Vec<String> {
+        let rules = self.machine.rules();
+        let mut terminals: Vec<Terminal> = rules.iter()
+            .flat_map(|rule| rule.output.data.iter())
+            .filter_map(|symbol| match symbol {
+                MixedChar::Terminal(terminal) if *terminal != Terminal::end() => Some(*terminal),
+                _ => None,
+            })
+            .chain([Terminal::end()])
+            .collect();
+        terminals.sort();
+        terminals.dedup();
+        let mut variables: Vec<Variable> = rules.iter().map(|rule| rule.clause).filter(|variable| variable.symbol != syntax::END_VARIABLE).collect();
+        variables.sort();
+        variables.dedup();
+
+        let mut lines = vec![format!("state {} actions", state), format!("    next  {}", self.history[self.current].step(self.machine).describe())];
+        // an action of a machine looking at more terminals depends on more than the next one
+        if self.machine.lookahead() == 1 {
+            for terminal in terminals {
+                match self.machine.next_action(state, Some(terminal)) {
+                    Action::Reject => {}
+                    action => lines.push(format!("    {:4}  {}", terminal, self.action_text(action))),
+                }
+            }
+        }
+        for variable in variables {
+            match self.machine.reduce_state(state, variable) {
+                0 => {}
+                next => lines.push(format!("    {:4}  goto {}", variable, next)),
+            }
+        }
+        lines
+    }
+
+    fn items_pane(&self, state: usize) -> Vec<String> {
+        let items = self.items.get(state).map_or(&[][..], Vec::as_slice);
+        [format!("state {} items", state)].into_iter()
+            .chain(items.iter().map(|item| format!("    {}", item)))
+            .collect()
+    }
+
+    /// The whole screen, `style` paints the part of the stack the current step pops
+    pub fn render(&self, style: Style) -> String {
+        let mut output = String::new();
+        let state = self.history[self.current].states().last().copied().unwrap_or_default();
+        writeln!(output, "input {:?}, step {} of {} computed", self.input, self.current, self.history.len() - 1).unwrap();
+        write!(output, "{}", self.trace_pane(style)).unwrap();
+
+        let left = self.items_pane(state);
+        let right = self.action_pane(state);
+        let width = left.iter().map(|line| line.chars().count()).max().unwrap_or_default() + 4;
+        for index in 0..left.len().max(right.len()) {
+            let line = format!("{:width$}{}", left.get(index).map_or("", String::as_str), right.get(index).map_or("", String::as_str), width = width);
+            writeln!(output, "{}", line.trim_end()).unwrap();
+        }
+        writeln!(output).unwrap();
+
+        let states: Vec<String> = self.state_breakpoints.iter().map(usize::to_string).collect();
+        let rules: Vec<String> = self.rule_breakpoints.iter().map(|rule| trace::rule_text(&self.machine.rules()[*rule])).collect();
+        if !states.is_empty() || !rules.is_empty() {
+            writeln!(output, "breakpoints: states [{}] rules [{}]", states.join(", "), rules.join(", ")).unwrap();
+        }
+        if !self.message.is_empty() {
+            writeln!(output, "{}", self.message).unwrap();
+        }
+        match &self.prompt {
+            Some((Prompt::Jump, line)) => writeln!(output, "go to step: {}", line).unwrap(),
+            Some((Prompt::Breakpoint, line)) => writeln!(output, "toggle breakpoint on state or rule (like 5 or A:xB, empty clears): {}", line).unwrap(),
+            Some((Prompt::Edit, line)) => writeln!(output, "new input: {}", line).unwrap(),
+            None => writeln!(output, "right/left arrow: step, up: reset, down/q: exit, c: run to breakpoint, g: go to step, b: breakpoint, e: edit input").unwrap(),
+        }
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use getch_rs::Key;
+
+    use super::Viewer;
+    use crate::parsing::tests::{lr_one, process, EXPRESSION};
+    use crate::parsing_table::lr_one::StateMachine;
+
+    fn viewer(machine: &StateMachine) -> Viewer<'_, StateMachine> {
+        let read = Box::new(|line: &str| match line.contains('x') {
+            true => Err(format!("no token starts with 'x' in {:?}", line)),
+            false => Ok(process(line)),
+        });
+        Viewer::new(machine, vec![], "a+a".to_string(), process("a+a"), read)
+    }
+
+    /// Opens the prompt of `key`, types `line` and enters it
+    fn prompt(viewer: &mut Viewer<StateMachine>, key: char, line: &str) {
+        for key in [key].into_iter().chain(line.chars()).chain(['\r']) {
+            assert!(viewer.handle(Key::Char(key)));
+        }
+    }
+
+    #[test]
+    fn stepping() {
+        let machine = lr_one(&EXPRESSION);
+        let mut viewer = viewer(&machine);
+        for key in [Key::Right, Key::Char(' '), Key::Right, Key::Left] {
+            assert!(viewer.handle(key));
+        }
+        assert_eq!(viewer.current, 2);
+        viewer.handle(Key::Up);
+        assert_eq!(viewer.current, 0);
+        viewer.handle(Key::Left);
+        assert_eq!(viewer.current, 0);
+        // a+a is accepted at step 7
+        for _ in 0..8 {
+            viewer.handle(Key::Right);
+        }
+        assert_eq!((viewer.current, viewer.message.as_str()), (7, "the parse has ended"));
+        assert!(!viewer.handle(Key::Char('q')));
+    }
+
+    #[test]
+    fn breakpoints() {
+        let machine = lr_one(&EXPRESSION);
+        let mut viewer = viewer(&machine);
+        prompt(&mut viewer, 'b', "3");
+        assert_eq!((viewer.state_breakpoints.iter().copied().collect::<Vec<_>>(), viewer.message.as_str()), (vec![3], "breakpoint set on 3"));
+        viewer.handle(Key::Char('c'));
+        assert_eq!((viewer.current, viewer.message.as_str()), (2, "breakpoint at step 2"));
+
+        prompt(&mut viewer, 'b', "T:a");
+        assert_eq!(viewer.rule_breakpoints.iter().copied().collect::<Vec<_>>(), [3]);
+        viewer.handle(Key::Char('c'));
+        assert_eq!((viewer.current, viewer.message.as_str()), (5, "breakpoint at step 5"));
+
+        prompt(&mut viewer, 'b', "3");
+        assert_eq!((viewer.state_breakpoints.len(), viewer.message.as_str()), (0, "breakpoint removed from 3"));
+        prompt(&mut viewer, 'b', "99");
+        assert_eq!(viewer.message, "there is no state 99");
+        prompt(&mut viewer, 'b', "T:b");
+        assert_eq!(viewer.message, "there is no rule T -> b");
+        prompt(&mut viewer, 'b', "");
+        assert!(viewer.rule_breakpoints.is_empty());
+        viewer.handle(Key::Char('c'));
+        assert_eq!((viewer.current, viewer.message.as_str()), (7, "the parse ended at step 7"));
+    }
+
+    #[test]
+    fn jumps_and_edits() {
+        let machine = lr_one(&EXPRESSION);
+        let mut viewer = viewer(&machine);
+        prompt(&mut viewer, 'g', "6");
+        assert_eq!(viewer.current, 6);
+        prompt(&mut viewer, 'g', "1");
+        assert_eq!(viewer.current, 1);
+        prompt(&mut viewer, 'g', "50");
+        assert_eq!((viewer.current, viewer.message.as_str()), (7, "the parse ended at step 7"));
+        prompt(&mut viewer, 'g', "six");
+        assert_eq!(viewer.message, "\"six\" is not a step");
+
+        // the prompt starts with the old input, escape leaves it untouched
+        viewer.handle(Key::Char('e'));
+        viewer.handle(Key::Backspace);
+        viewer.handle(Key::Esc);
+        assert_eq!((viewer.input.as_str(), viewer.current), ("a+a", 7));
+        prompt(&mut viewer, 'e', "x");
+        assert_eq!((viewer.input.as_str(), viewer.message.as_str()), ("a+a", "no token starts with 'x' in \"a+ax\""));
+        viewer.handle(Key::Char('e'));
+        for _ in 0..2 {
+            viewer.handle(Key::Backspace);
+        }
+        viewer.handle(Key::Char('\r'));
+        assert_eq!((viewer.input.as_str(), viewer.current, viewer.message.as_str()), ("a", 0, "input replaced"));
+        prompt(&mut viewer, 'g', "10");
+        assert_eq!(viewer.current, 3);
+    }
+}